@@ -0,0 +1,699 @@
+use cosmwasm_std::{
+    attr, to_binary, Addr, Binary, Deps, DepsMut, Empty, Env, MessageInfo, Order, Reply, ReplyOn,
+    Response, StdError, StdResult, SubMsg, WasmMsg,
+};
+
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+
+use crate::error::ContractError;
+use crate::msg::{
+    ConfigResponse, ExecuteMsg, InstantiateMsg, QueryMsg, SaleInfo, SalesByStatusResponse,
+    SalesResponse,
+};
+use crate::state::{
+    Config, SaleRecord, CONFIG, PENDING_SALE, SALES, SALES_BY_CREATOR, SALES_BY_CW20,
+};
+
+use cw2::set_contract_version;
+use cw_storage_plus::Bound;
+use sale::msg::InfoResponse;
+use sale::state::Phase;
+
+// settings for pagination
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+// version info for migration info
+const CONTRACT_NAME: &str = "crates.io:sale-factory";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// reply id of the sale instantiation submessage
+const INSTANTIATE_SALE_REPLY_ID: u64 = 1;
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    let config = Config {
+        owner: info.sender,
+        sale_code_id: msg.sale_code_id,
+    };
+    CONFIG.save(deps.storage, &config)?;
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::default())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::CreateSale { sale, label } => try_create_sale(deps, env, info, sale, label),
+        ExecuteMsg::UpdateConfig { sale_code_id } => try_update_config(deps, info, sale_code_id),
+    }
+}
+
+pub fn try_create_sale(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    sale: Box<sale::msg::InstantiateMsg>,
+    label: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    deps.api.addr_validate(sale.cw20_address.as_str())?;
+
+    PENDING_SALE.save(
+        deps.storage,
+        &SaleRecord {
+            creator: info.sender.clone(),
+            cw20_address: sale.cw20_address.clone(),
+            label: label.clone(),
+        },
+    )?;
+
+    let instantiate = WasmMsg::Instantiate {
+        admin: Some(info.sender.to_string()),
+        code_id: config.sale_code_id,
+        msg: to_binary(&sale)?,
+        send: info.funds,
+        label: label.clone(),
+    };
+
+    Ok(Response {
+        messages: vec![],
+        submessages: vec![SubMsg {
+            id: INSTANTIATE_SALE_REPLY_ID,
+            msg: instantiate.into(),
+            gas_limit: None,
+            reply_on: ReplyOn::Success,
+        }],
+        attributes: vec![
+            attr("action", "create_sale"),
+            attr("factory", env.contract.address),
+            attr("creator", info.sender),
+            attr("label", label),
+        ],
+        data: None,
+    })
+}
+
+pub fn try_update_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    sale_code_id: u64,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    if info.sender != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    config.sale_code_id = sale_code_id;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response {
+        messages: vec![],
+        submessages: vec![],
+        attributes: vec![
+            attr("action", "update_config"),
+            attr("sale_code_id", sale_code_id),
+        ],
+        data: None,
+    })
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    if msg.id != INSTANTIATE_SALE_REPLY_ID {
+        return Err(ContractError::UnknownReplyId { id: msg.id });
+    }
+    let data = msg
+        .result
+        .into_result()
+        .map_err(StdError::generic_err)?
+        .data
+        .ok_or(ContractError::InvalidReplyData {})?;
+    let address = deps.api.addr_validate(&parse_contract_address(&data)?)?;
+
+    let record = match PENDING_SALE.may_load(deps.storage)? {
+        Some(record) => record,
+        None => return Err(ContractError::NoPendingSale {}),
+    };
+    PENDING_SALE.remove(deps.storage);
+
+    SALES.save(deps.storage, &address, &record)?;
+    SALES_BY_CREATOR.save(deps.storage, (&record.creator, &address), &Empty {})?;
+    SALES_BY_CW20.save(deps.storage, (&record.cw20_address, &address), &Empty {})?;
+
+    // the factory instantiated the sale so it is the owner until the creator accepts
+    let propose = WasmMsg::Execute {
+        contract_addr: address.to_string(),
+        msg: to_binary(&sale::msg::ExecuteMsg::ProposeNewOwner {
+            owner: record.creator.to_string(),
+            expiry: None,
+        })?,
+        send: vec![],
+    };
+
+    Ok(Response {
+        messages: vec![propose.into()],
+        submessages: vec![],
+        attributes: vec![
+            attr("action", "register_sale"),
+            attr("sale_address", address),
+            attr("creator", record.creator),
+        ],
+        data: None,
+    })
+}
+
+/// Reads the contract address out of a protobuf `MsgInstantiateContractResponse`,
+/// whose first field is the address string.
+fn parse_contract_address(data: &[u8]) -> Result<String, ContractError> {
+    if data.first() != Some(&0x0a) {
+        return Err(ContractError::InvalidReplyData {});
+    }
+    let mut len: usize = 0;
+    let mut pos = 1;
+    loop {
+        let byte = *data.get(pos).ok_or(ContractError::InvalidReplyData {})?;
+        len |= ((byte & 0x7f) as usize) << (7 * (pos - 1));
+        pos += 1;
+        if byte & 0x80 == 0 {
+            break;
+        }
+        if pos > 4 {
+            return Err(ContractError::InvalidReplyData {});
+        }
+    }
+    let bytes = data
+        .get(pos..pos + len)
+        .ok_or(ContractError::InvalidReplyData {})?;
+    String::from_utf8(bytes.to_vec()).map_err(|_| ContractError::InvalidReplyData {})
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::Sale { address } => to_binary(&query_sale(deps, address)?),
+        QueryMsg::ListSales { start_after, limit } => {
+            to_binary(&query_list_sales(deps, start_after, limit)?)
+        }
+        QueryMsg::SalesByCreator {
+            creator,
+            start_after,
+            limit,
+        } => to_binary(&query_sales_by_creator(deps, creator, start_after, limit)?),
+        QueryMsg::SalesByCw20 {
+            cw20_address,
+            start_after,
+            limit,
+        } => to_binary(&query_sales_by_cw20(
+            deps,
+            cw20_address,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::SalesByStatus {
+            phase,
+            start_after,
+            limit,
+        } => to_binary(&query_sales_by_status(deps, phase, start_after, limit)?),
+    }
+}
+
+fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    Ok(ConfigResponse {
+        owner: config.owner,
+        sale_code_id: config.sale_code_id,
+    })
+}
+
+fn query_sale(deps: Deps, address: String) -> StdResult<SaleInfo> {
+    let address = deps.api.addr_validate(&address)?;
+    let record = SALES.load(deps.storage, &address)?;
+    Ok(sale_info(address, record))
+}
+
+fn query_list_sales(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<SalesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let sales = SALES
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (address, record) = item?;
+            Ok(sale_info(
+                Addr::unchecked(String::from_utf8(address)?),
+                record,
+            ))
+        })
+        .collect::<StdResult<Vec<SaleInfo>>>()?;
+
+    Ok(SalesResponse { sales })
+}
+
+fn query_sales_by_creator(
+    deps: Deps,
+    creator: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<SalesResponse> {
+    let creator = deps.api.addr_validate(&creator)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let addresses = SALES_BY_CREATOR
+        .prefix(&creator)
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect();
+    load_sales(deps, addresses)
+}
+
+fn query_sales_by_cw20(
+    deps: Deps,
+    cw20_address: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<SalesResponse> {
+    let cw20_address = deps.api.addr_validate(&cw20_address)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let addresses = SALES_BY_CW20
+        .prefix(&cw20_address)
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect();
+    load_sales(deps, addresses)
+}
+
+/// Scans the sales after `start_after`, querying each for its current phase. The phase is
+/// not stored by the factory as it changes with the block time.
+fn query_sales_by_status(
+    deps: Deps,
+    phase: Phase,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<SalesByStatusResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    // every sale checked costs a query, so at most limit are checked per call
+    let mut sales = vec![];
+    let mut last_scanned = None;
+    let mut scanned = 0;
+    for item in SALES
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+    {
+        let (address, record) = item?;
+        let address = Addr::unchecked(String::from_utf8(address)?);
+        let info: InfoResponse = deps
+            .querier
+            .query_wasm_smart(&address, &sale::msg::QueryMsg::GetInfo {})?;
+        if info.phase == phase {
+            sales.push(sale_info(address.clone(), record));
+        }
+        last_scanned = Some(address);
+        scanned += 1;
+    }
+    if scanned < limit {
+        last_scanned = None;
+    }
+
+    Ok(SalesByStatusResponse {
+        sales,
+        last_scanned,
+    })
+}
+
+fn load_sales(deps: Deps, addresses: Vec<Vec<u8>>) -> StdResult<SalesResponse> {
+    let sales = addresses
+        .into_iter()
+        .map(|address| {
+            let address = Addr::unchecked(String::from_utf8(address)?);
+            let record = SALES.load(deps.storage, &address)?;
+            Ok(sale_info(address, record))
+        })
+        .collect::<StdResult<Vec<SaleInfo>>>()?;
+
+    Ok(SalesResponse { sales })
+}
+
+fn sale_info(address: Addr, record: SaleRecord) -> SaleInfo {
+    SaleInfo {
+        address,
+        creator: record.creator,
+        cw20_address: record.cw20_address,
+        label: record.label,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::{mock_env, mock_info, MockApi, MockQuerier, MockStorage};
+    use cosmwasm_std::{
+        coins, from_binary, from_slice, ContractResult, CosmosMsg, OwnedDeps, Querier,
+        QuerierResult, QueryRequest, SubcallResponse, SystemResult, Uint128, WasmQuery,
+    };
+    use sale::state::Status;
+    use std::collections::HashMap;
+
+    /// Answers sale `GetInfo` queries with the phase recorded for each address.
+    struct SaleQuerier {
+        base: MockQuerier,
+        phases: HashMap<String, Phase>,
+    }
+
+    impl Querier for SaleQuerier {
+        fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+            let request: QueryRequest<Empty> = from_slice(bin_request).unwrap();
+            match request {
+                QueryRequest::Wasm(WasmQuery::Smart { contract_addr, .. }) => {
+                    let info = InfoResponse {
+                        owner: Addr::unchecked("creator"),
+                        cw20_address: Addr::unchecked("cw20"),
+                        balance: Uint128(0),
+                        start: None,
+                        end: None,
+                        phase: self.phases[&contract_addr],
+                        max_per_address: None,
+                        min_per_purchase: None,
+                        hard_cap: None,
+                        tokens_sold: Uint128(0),
+                        merkle_root: None,
+                        vesting: None,
+                        pricing: None,
+                        batch_auction: None,
+                        fair_launch: None,
+                        soft_cap: None,
+                        escrow_proceeds: false,
+                        split: None,
+                        fee: None,
+                        referral: None,
+                        status: Status::Initialized,
+                        live_balance: false,
+                        paused: false,
+                        guardian: None,
+                    };
+                    SystemResult::Ok(ContractResult::Ok(to_binary(&info).unwrap()))
+                }
+                _ => self.base.handle_query(&request),
+            }
+        }
+    }
+
+    fn mock_deps() -> OwnedDeps<MockStorage, MockApi, SaleQuerier> {
+        OwnedDeps {
+            storage: MockStorage::default(),
+            api: MockApi::default(),
+            querier: SaleQuerier {
+                base: MockQuerier::new(&[]),
+                phases: HashMap::new(),
+            },
+        }
+    }
+
+    fn sale_msg(cw20_address: &str) -> sale::msg::InstantiateMsg {
+        sale::msg::InstantiateMsg {
+            cw20_address: Addr::unchecked(cw20_address),
+            denom: Some(String::from("ujuno")),
+            price: Some(Uint128(10)),
+            start: None,
+            end: None,
+            max_per_address: None,
+            min_per_purchase: None,
+            hard_cap: None,
+            merkle_root: None,
+            vesting: None,
+            pricing: None,
+            batch_auction: None,
+            fair_launch: None,
+            soft_cap: None,
+            escrow_proceeds: false,
+            split: None,
+            fee: None,
+            referral: None,
+            live_balance: false,
+            guardian: None,
+        }
+    }
+
+    fn instantiate_reply(address: &str) -> Reply {
+        let mut data = vec![0x0a, address.len() as u8];
+        data.extend_from_slice(address.as_bytes());
+        Reply {
+            id: INSTANTIATE_SALE_REPLY_ID,
+            result: ContractResult::Ok(SubcallResponse {
+                events: vec![],
+                data: Some(Binary(data)),
+            }),
+        }
+    }
+
+    fn create_sale(mut deps: DepsMut, creator: &str, cw20_address: &str, address: &str) {
+        let msg = ExecuteMsg::CreateSale {
+            sale: Box::new(sale_msg(cw20_address)),
+            label: format!("{} sale", cw20_address),
+        };
+        execute(deps.branch(), mock_env(), mock_info(creator, &[]), msg).unwrap();
+        reply(deps, mock_env(), instantiate_reply(address)).unwrap();
+    }
+
+    fn sale_addresses(res: Binary) -> Vec<String> {
+        let res: SalesResponse = from_binary(&res).unwrap();
+        res.sales
+            .into_iter()
+            .map(|sale| sale.address.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn proper_initialization() {
+        let mut deps = mock_deps();
+        let msg = InstantiateMsg { sale_code_id: 7 };
+        instantiate(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+        let config: ConfigResponse = from_binary(&res).unwrap();
+        assert_eq!(
+            config,
+            ConfigResponse {
+                owner: Addr::unchecked("owner"),
+                sale_code_id: 7
+            }
+        );
+
+        // only the owner can change the code id
+        let msg = ExecuteMsg::UpdateConfig { sale_code_id: 8 };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            msg.clone(),
+        );
+        assert!(matches!(err, Err(ContractError::Unauthorized {})));
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+        let config: ConfigResponse = from_binary(&res).unwrap();
+        assert_eq!(config.sale_code_id, 8);
+    }
+
+    #[test]
+    fn create_sale_instantiates_and_registers() {
+        let mut deps = mock_deps();
+        let msg = InstantiateMsg { sale_code_id: 7 };
+        instantiate(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::CreateSale {
+            sale: Box::new(sale_msg("cw20")),
+            label: String::from("cw20 sale"),
+        };
+        let info = mock_info("creator", &coins(5, "ujuno"));
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(1, res.submessages.len());
+        let submsg = &res.submessages[0];
+        assert_eq!(submsg.id, INSTANTIATE_SALE_REPLY_ID);
+        assert_eq!(submsg.reply_on, ReplyOn::Success);
+        assert_eq!(
+            submsg.msg,
+            CosmosMsg::Wasm(WasmMsg::Instantiate {
+                admin: Some(String::from("creator")),
+                code_id: 7,
+                msg: to_binary(&sale_msg("cw20")).unwrap(),
+                send: coins(5, "ujuno"),
+                label: String::from("cw20 sale"),
+            })
+        );
+
+        // unknown reply ids are rejected
+        let mut unknown = instantiate_reply("sale0");
+        unknown.id = 99;
+        let err = reply(deps.as_mut(), mock_env(), unknown);
+        assert!(matches!(err, Err(ContractError::UnknownReplyId { id: 99 })));
+
+        let res = reply(deps.as_mut(), mock_env(), instantiate_reply("sale0")).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: String::from("sale0"),
+                msg: to_binary(&sale::msg::ExecuteMsg::ProposeNewOwner {
+                    owner: String::from("creator"),
+                    expiry: None,
+                })
+                .unwrap(),
+                send: vec![],
+            })]
+        );
+
+        let msg = QueryMsg::Sale {
+            address: String::from("sale0"),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let sale: SaleInfo = from_binary(&res).unwrap();
+        assert_eq!(
+            sale,
+            SaleInfo {
+                address: Addr::unchecked("sale0"),
+                creator: Addr::unchecked("creator"),
+                cw20_address: Addr::unchecked("cw20"),
+                label: String::from("cw20 sale"),
+            }
+        );
+
+        // the pending record is consumed by the reply
+        let err = reply(deps.as_mut(), mock_env(), instantiate_reply("sale1"));
+        assert!(matches!(err, Err(ContractError::NoPendingSale {})));
+    }
+
+    #[test]
+    fn parse_reply_data() {
+        // lengths of 128 and over take a second varint byte
+        let address = "a".repeat(130);
+        let mut data = vec![0x0a, 0x82, 0x01];
+        data.extend_from_slice(address.as_bytes());
+        assert_eq!(parse_contract_address(&data).unwrap(), address);
+
+        assert!(parse_contract_address(&[]).is_err());
+        assert!(parse_contract_address(&[0x12, 0x01, b'a']).is_err());
+        assert!(parse_contract_address(&[0x0a, 0x05, b'a']).is_err());
+    }
+
+    #[test]
+    fn query_sales_by_index() {
+        let mut deps = mock_deps();
+        let msg = InstantiateMsg { sale_code_id: 7 };
+        instantiate(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+
+        create_sale(deps.as_mut(), "alice", "token_a", "sale0");
+        create_sale(deps.as_mut(), "bob", "token_a", "sale1");
+        create_sale(deps.as_mut(), "alice", "token_b", "sale2");
+        create_sale(deps.as_mut(), "alice", "token_a", "sale3");
+
+        let msg = QueryMsg::ListSales {
+            start_after: Some(String::from("sale0")),
+            limit: Some(2),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        assert_eq!(sale_addresses(res), vec!["sale1", "sale2"]);
+
+        let msg = QueryMsg::SalesByCreator {
+            creator: String::from("alice"),
+            start_after: None,
+            limit: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        assert_eq!(sale_addresses(res), vec!["sale0", "sale2", "sale3"]);
+
+        let msg = QueryMsg::SalesByCreator {
+            creator: String::from("alice"),
+            start_after: Some(String::from("sale0")),
+            limit: Some(1),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        assert_eq!(sale_addresses(res), vec!["sale2"]);
+
+        let msg = QueryMsg::SalesByCw20 {
+            cw20_address: String::from("token_a"),
+            start_after: None,
+            limit: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        assert_eq!(sale_addresses(res), vec!["sale0", "sale1", "sale3"]);
+    }
+
+    #[test]
+    fn query_sales_by_phase() {
+        let mut deps = mock_deps();
+        let msg = InstantiateMsg { sale_code_id: 7 };
+        instantiate(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+
+        let phases = [Phase::Open, Phase::Ended, Phase::Open, Phase::Pending];
+        for (i, phase) in phases.iter().enumerate() {
+            let address = format!("sale{}", i);
+            create_sale(deps.as_mut(), "alice", "token_a", &address);
+            deps.querier.phases.insert(address, *phase);
+        }
+
+        let msg = QueryMsg::SalesByStatus {
+            phase: Phase::Open,
+            start_after: None,
+            limit: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let res: SalesByStatusResponse = from_binary(&res).unwrap();
+        assert_eq!(res.sales.len(), 2);
+        assert_eq!(res.last_scanned, None);
+
+        // only limit sales are checked, whatever their phase
+        let mut start_after = Some(String::from("sale0"));
+        let mut pages = vec![];
+        while let Some(cursor) = start_after {
+            let msg = QueryMsg::SalesByStatus {
+                phase: Phase::Open,
+                start_after: Some(cursor),
+                limit: Some(1),
+            };
+            let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+            let res: SalesByStatusResponse = from_binary(&res).unwrap();
+            pages.push(
+                res.sales
+                    .into_iter()
+                    .map(|sale| sale.address.to_string())
+                    .collect::<Vec<String>>(),
+            );
+            start_after = res.last_scanned.map(String::from);
+        }
+        assert_eq!(
+            pages,
+            vec![vec![], vec![String::from("sale2")], vec![], vec![]]
+        );
+
+        let msg = QueryMsg::SalesByStatus {
+            phase: Phase::Ended,
+            start_after: None,
+            limit: Some(2),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let res: SalesByStatusResponse = from_binary(&res).unwrap();
+        assert_eq!(res.sales[0].address, "sale1");
+        assert_eq!(res.last_scanned, Some(Addr::unchecked("sale1")));
+    }
+}
@@ -1,5 +1,4 @@
 pub mod contract;
 pub mod error;
-mod integration_test;
 pub mod msg;
 pub mod state;
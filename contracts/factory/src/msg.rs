@@ -0,0 +1,87 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::Addr;
+
+use sale::state::Phase;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InstantiateMsg {
+    pub sale_code_id: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecuteMsg {
+    // CreateSale instantiates a new sale with the sender as its admin. The factory proposes
+    // the sender as the sale owner, who completes the handover with AcceptOwnership
+    CreateSale {
+        sale: Box<sale::msg::InstantiateMsg>,
+        label: String,
+    },
+    // UpdateConfig changes the code id used for new sales, owner only
+    UpdateConfig {
+        sale_code_id: u64,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    // Config returns the factory owner and sale code id
+    Config {},
+    // Sale returns a single sale created by the factory
+    Sale {
+        address: String,
+    },
+    // ListSales returns every sale created by the factory, ordered by address
+    ListSales {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    // SalesByCreator returns the sales created by an address
+    SalesByCreator {
+        creator: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    // SalesByCw20 returns the sales of a cw20 token
+    SalesByCw20 {
+        cw20_address: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    // SalesByStatus checks up to limit sales for the given phase, querying each sale.
+    // Pass the returned last_scanned as start_after to check the next ones
+    SalesByStatus {
+        phase: Phase,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ConfigResponse {
+    pub owner: Addr,
+    pub sale_code_id: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SaleInfo {
+    pub address: Addr,
+    pub creator: Addr,
+    pub cw20_address: Addr,
+    pub label: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SalesResponse {
+    pub sales: Vec<SaleInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SalesByStatusResponse {
+    pub sales: Vec<SaleInfo>,
+    /// last sale checked, unset once every sale has been checked
+    pub last_scanned: Option<Addr>,
+}
@@ -0,0 +1,33 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{Addr, Empty};
+use cw_storage_plus::{Item, Map};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Config {
+    pub owner: Addr,
+    /// code id used to instantiate new sales
+    pub sale_code_id: u64,
+}
+
+pub const CONFIG: Item<Config> = Item::new("config");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SaleRecord {
+    pub creator: Addr,
+    pub cw20_address: Addr,
+    pub label: String,
+}
+
+/// Sale being instantiated, picked up by the reply once its address is known.
+pub const PENDING_SALE: Item<SaleRecord> = Item::new("pending_sale");
+
+/// Every sale created by the factory, keyed by sale address.
+pub const SALES: Map<&Addr, SaleRecord> = Map::new("sales");
+
+/// Sales indexed by (creator, sale address).
+pub const SALES_BY_CREATOR: Map<(&Addr, &Addr), Empty> = Map::new("sales_by_creator");
+
+/// Sales indexed by (cw20 address, sale address).
+pub const SALES_BY_CW20: Map<(&Addr, &Addr), Empty> = Map::new("sales_by_cw20");
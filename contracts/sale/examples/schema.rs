@@ -0,0 +1,41 @@
+use std::env::current_dir;
+use std::fs::create_dir_all;
+
+use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
+
+use sale::msg::{
+    AllowanceResponse, BidResponse, ClearingResponse, CurrentPriceResponse, Cw20PricesResponse,
+    DepositResponse, ExecuteMsg, FairLaunchResponse, InfoResponse, InstantiateMsg, MigrateMsg,
+    PendingOwnerResponse, PricesResponse, ProceedsResponse, QueryMsg, ReceiveMsg,
+    ReconcileResponse, ReferralStatsResponse, TiersResponse, VestingInfoResponse,
+};
+use sale::state::State;
+
+fn main() {
+    let mut out_dir = current_dir().unwrap();
+    out_dir.push("schema");
+    create_dir_all(&out_dir).unwrap();
+    remove_schemas(&out_dir).unwrap();
+
+    export_schema(&schema_for!(InstantiateMsg), &out_dir);
+    export_schema(&schema_for!(ExecuteMsg), &out_dir);
+    export_schema(&schema_for!(QueryMsg), &out_dir);
+    export_schema(&schema_for!(ReceiveMsg), &out_dir);
+    export_schema(&schema_for!(MigrateMsg), &out_dir);
+    export_schema(&schema_for!(State), &out_dir);
+    export_schema(&schema_for!(InfoResponse), &out_dir);
+    export_schema(&schema_for!(PendingOwnerResponse), &out_dir);
+    export_schema(&schema_for!(PricesResponse), &out_dir);
+    export_schema(&schema_for!(Cw20PricesResponse), &out_dir);
+    export_schema(&schema_for!(AllowanceResponse), &out_dir);
+    export_schema(&schema_for!(VestingInfoResponse), &out_dir);
+    export_schema(&schema_for!(CurrentPriceResponse), &out_dir);
+    export_schema(&schema_for!(TiersResponse), &out_dir);
+    export_schema(&schema_for!(ClearingResponse), &out_dir);
+    export_schema(&schema_for!(BidResponse), &out_dir);
+    export_schema(&schema_for!(FairLaunchResponse), &out_dir);
+    export_schema(&schema_for!(DepositResponse), &out_dir);
+    export_schema(&schema_for!(ProceedsResponse), &out_dir);
+    export_schema(&schema_for!(ReferralStatsResponse), &out_dir);
+    export_schema(&schema_for!(ReconcileResponse), &out_dir);
+}
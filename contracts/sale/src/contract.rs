@@ -0,0 +1,5736 @@
+use cosmwasm_std::{
+    attr, coins, from_binary, to_binary, Addr, Api, BankMsg, Binary, Coin, CosmosMsg, Deps,
+    DepsMut, Env, MessageInfo, Order, Response, StdError, StdResult, Storage, Timestamp, Uint128,
+    WasmMsg,
+};
+
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+
+use crate::error::ContractError;
+use crate::merkle::{leaf_hash, validate_root, verify_proof, Allocation};
+use crate::migrations::{migrate_from_v0_1_0, parse_version, v0_1_0};
+use crate::msg::{
+    AllowanceResponse, BidResponse, ClearingResponse, CurrentPriceResponse, Cw20PricesResponse,
+    DepositResponse, ExecuteMsg, FairLaunchResponse, InfoResponse, InstantiateMsg, MigrateMsg,
+    PendingOwnerResponse, PricesResponse, ProceedsResponse, QueryMsg, ReceiveMsg,
+    ReconcileResponse, ReferralStatsResponse, TierInfo, TiersResponse, VestingInfoResponse,
+};
+use crate::pricing::Pricing;
+use crate::state::{
+    Bid, Clearing, FairLaunch, FairLaunchTotals, Fee, PendingOwner, Phase, ProceedsSplit,
+    ReferralReward, State, Status, BIDS, CLEARING, CW20_PRICES, CW20_PROCEEDS, DEMAND, DEPOSITS,
+    FAIR_LAUNCH, FEES, HELD, MAX_BPS, OWED, PAYMENTS, PENDING_OWNER, PRICES, PROCEEDS, PURCHASES,
+    REFERRALS, SETTLEMENTS, STATE, VESTING,
+};
+
+use cw0::Expiration;
+use cw2::{set_contract_version, ContractVersion, CONTRACT};
+use cw20::{BalanceResponse, Cw20Coin, Cw20ExecuteMsg, Cw20QueryMsg, Cw20ReceiveMsg};
+use cw_storage_plus::{Bound, Map, U128Key};
+use std::cmp::Ordering;
+
+// settings for pagination
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+// version info for migration info
+const CONTRACT_NAME: &str = "crates.io:sale";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// Note, you can use StdResult in some functions where you do not
+// make use of the custom errors
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    if msg.denom.is_some() != msg.price.is_some() {
+        return Err(ContractError::IncompletePrice {});
+    }
+    if msg.price == Some(Uint128(0)) {
+        return Err(ContractError::InvalidZeroPrice {});
+    }
+    validate_sale_window(&env, msg.start, msg.end)?;
+    if let Some(schedule) = &msg.vesting {
+        if !schedule.is_valid() {
+            return Err(ContractError::InvalidVestingSchedule {});
+        }
+    }
+    if let Some(root) = &msg.merkle_root {
+        validate_root(root)?;
+    }
+    if let Some(pricing) = &msg.pricing {
+        pricing.validate()?;
+    }
+    if let Some(auction) = &msg.batch_auction {
+        // bids are not checked against an allowlist
+        if msg.end.is_none()
+            || msg.pricing.is_some()
+            || msg.merkle_root.is_some()
+            || auction.reserve_price == Uint128(0)
+        {
+            return Err(ContractError::InvalidBatchAuction {});
+        }
+    }
+    if let Some(launch) = &msg.fair_launch {
+        if msg.end.is_none()
+            || msg.pricing.is_some()
+            || msg.batch_auction.is_some()
+            || msg.merkle_root.is_some()
+            || launch.price == Uint128(0)
+        {
+            return Err(ContractError::InvalidFairLaunch {});
+        }
+    }
+    if let Some(split) = &msg.split {
+        validate_split(deps.api, split)?;
+    }
+    if let Some(fee) = &msg.fee {
+        if fee.bps > MAX_BPS {
+            return Err(ContractError::InvalidFee {});
+        }
+        deps.api.addr_validate(fee.collector.as_str())?;
+    }
+    if let Some(guardian) = &msg.guardian {
+        deps.api.addr_validate(guardian.as_str())?;
+    }
+    if let Some(referral) = &msg.referral {
+        // rewards could not be clawed back from a failed sale
+        if referral.bps > MAX_BPS || msg.soft_cap.is_some() {
+            return Err(ContractError::InvalidReferral {});
+        }
+    }
+    if let Some(soft_cap) = msg.soft_cap {
+        if msg.end.is_none()
+            || msg.batch_auction.is_some()
+            || msg.fair_launch.is_some()
+            || soft_cap == Uint128(0)
+            || msg.hard_cap.map_or(false, |hard_cap| soft_cap > hard_cap)
+        {
+            return Err(ContractError::InvalidSoftCap {});
+        }
+    }
+
+    let state = State {
+        owner: info.sender,
+        cw20_address: msg.cw20_address,
+        balance: Uint128(0),
+        start: msg.start,
+        end: msg.end,
+        max_per_address: msg.max_per_address,
+        min_per_purchase: msg.min_per_purchase,
+        hard_cap: msg.hard_cap,
+        tokens_sold: Uint128(0),
+        merkle_root: msg.merkle_root,
+        vesting: msg.vesting,
+        pricing: msg.pricing,
+        batch_auction: msg.batch_auction,
+        fair_launch: msg.fair_launch,
+        soft_cap: msg.soft_cap,
+        escrow_proceeds: msg.escrow_proceeds,
+        split: msg.split,
+        fee: msg.fee,
+        referral: msg.referral,
+        status: Status::Uninitialized,
+        live_balance: msg.live_balance,
+        paused: false,
+        guardian: msg.guardian,
+    };
+    STATE.save(deps.storage, &state)?;
+    if let (Some(denom), Some(price)) = (msg.denom, msg.price) {
+        PRICES.save(deps.storage, &denom, &price)?;
+    }
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::default())
+}
+
+// And declare a custom Error variant for the ones where you will want to make use of it
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::SetPrice { denom, price } => try_set_price(
+            deps,
+            info.sender,
+            Coin {
+                denom,
+                amount: price,
+            },
+        ),
+        ExecuteMsg::RemovePrice { denom } => try_remove_price(deps, info.sender, denom),
+        ExecuteMsg::SetCw20Price { address, price } => {
+            try_set_cw20_price(deps, info.sender, address, price)
+        }
+        ExecuteMsg::RemoveCw20Price { address } => {
+            try_remove_cw20_price(deps, info.sender, address)
+        }
+        ExecuteMsg::Receive(msg) => try_receive(deps, env, info, msg),
+        ExecuteMsg::Buy { prices, referrer } => try_buy(deps, env, info, prices, None, referrer),
+        ExecuteMsg::WhitelistBuy {
+            prices,
+            allocation,
+            proof,
+        } => {
+            let allocation = Allocation {
+                amount: allocation,
+                proof,
+            };
+            try_buy(deps, env, info, prices, Some(allocation), None)
+        }
+        ExecuteMsg::SetMerkleRoot { merkle_root } => {
+            try_set_merkle_root(deps, info.sender, merkle_root)
+        }
+        ExecuteMsg::SetProceedsSplit { split } => try_set_proceeds_split(deps, info.sender, split),
+        ExecuteMsg::SetFee { bps, collector } => try_set_fee(deps, info.sender, bps, collector),
+        ExecuteMsg::ReleaseFees {} => try_release_fees(deps, env),
+        ExecuteMsg::WithdrawAll {} => try_withdraw_all(deps, env, info.sender),
+        ExecuteMsg::Withdraw {
+            amount,
+            recipient,
+            msg,
+        } => try_withdraw(deps, env, info.sender, amount, recipient, msg),
+        ExecuteMsg::Sync {} => try_sync(deps, env, info.sender),
+        ExecuteMsg::WithdrawProceeds {
+            denom,
+            amount,
+            recipient,
+        } => try_withdraw_proceeds(deps, env, info.sender, denom, amount, recipient),
+        ExecuteMsg::Claim {} => try_claim(deps, env, info.sender),
+        ExecuteMsg::Deposit {} => try_deposit(deps, env, info),
+        ExecuteMsg::Refund {} => try_refund(deps, env, info.sender),
+        ExecuteMsg::Bid { amount, max_price } => try_bid(deps, env, info, amount, max_price),
+        ExecuteMsg::Finalize { limit } => try_finalize(deps, env, limit),
+        ExecuteMsg::SettleBid {} => try_settle_bid(deps, info.sender),
+        ExecuteMsg::ProposeNewOwner { owner, expiry } => {
+            try_propose_new_owner(deps, env, info.sender, owner, expiry)
+        }
+        ExecuteMsg::AcceptOwnership {} => try_accept_ownership(deps, env, info.sender),
+        ExecuteMsg::CancelOwnershipProposal {} => try_cancel_ownership_proposal(deps, info.sender),
+        ExecuteMsg::SetSaleWindow { start, end } => {
+            try_set_sale_window(deps, env, info.sender, start, end)
+        }
+        ExecuteMsg::Pause {} => try_pause(deps, info.sender),
+        ExecuteMsg::Unpause {} => try_unpause(deps, info.sender),
+        ExecuteMsg::SetGuardian { guardian } => try_set_guardian(deps, info.sender, guardian),
+    }
+}
+
+fn validate_sale_window(
+    env: &Env,
+    start: Option<Expiration>,
+    end: Option<Expiration>,
+) -> Result<(), ContractError> {
+    if let Some(end) = end {
+        if end.is_expired(&env.block) {
+            return Err(ContractError::InvalidSaleWindow {});
+        }
+        // height and time based bounds cannot be compared
+        if let Some(Ordering::Greater) | Some(Ordering::Equal) =
+            start.and_then(|start| start.partial_cmp(&end))
+        {
+            return Err(ContractError::InvalidSaleWindow {});
+        }
+    }
+    Ok(())
+}
+
+fn validate_split(api: &dyn Api, split: &ProceedsSplit) -> Result<(), ContractError> {
+    if split.recipients.is_empty() || split.recipients.iter().any(|r| r.weight == 0) {
+        return Err(ContractError::InvalidProceedsSplit {});
+    }
+    for recipient in split.recipients.iter() {
+        api.addr_validate(recipient.address.as_str())?;
+    }
+    api.addr_validate(split.remainder.as_str())?;
+    Ok(())
+}
+
+/// Rejects purchases outside of the sale window.
+fn assert_sale_open(state: &State, env: &Env) -> Result<(), ContractError> {
+    if state.status == Status::Uninitialized {
+        return Err(ContractError::SaleUninitialized {});
+    }
+    match state.phase(&env.block) {
+        Phase::Pending => Err(ContractError::SaleNotStarted {}),
+        Phase::Open => Ok(()),
+        Phase::Ended => Err(ContractError::SaleEnded {}),
+    }
+}
+
+pub fn try_set_sale_window(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    start: Option<Expiration>,
+    end: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    let state = STATE.load(deps.storage)?;
+    if state.owner != sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    if state.phase(&env.block) != Phase::Pending {
+        return Err(ContractError::SaleStarted {});
+    }
+    validate_sale_window(&env, start, end)?;
+    // a batch auction is finalized once the sale ends
+    if state.batch_auction.is_some() && end.is_none() {
+        return Err(ContractError::InvalidBatchAuction {});
+    }
+    if state.fair_launch.is_some() && end.is_none() {
+        return Err(ContractError::InvalidFairLaunch {});
+    }
+    if state.soft_cap.is_some() && end.is_none() {
+        return Err(ContractError::InvalidSoftCap {});
+    }
+
+    STATE.update(deps.storage, |mut state| -> Result<_, ContractError> {
+        state.start = start;
+        state.end = end;
+        Ok(state)
+    })?;
+
+    Ok(Response::default())
+}
+
+pub fn try_set_proceeds_split(
+    deps: DepsMut,
+    sender: Addr,
+    split: Option<ProceedsSplit>,
+) -> Result<Response, ContractError> {
+    if STATE.load(deps.storage)?.owner != sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    if let Some(split) = &split {
+        validate_split(deps.api, split)?;
+    }
+
+    let recipients = split.as_ref().map_or(0, |split| split.recipients.len());
+    STATE.update(deps.storage, |mut state| -> Result<_, ContractError> {
+        state.split = split;
+        Ok(state)
+    })?;
+
+    Ok(Response {
+        messages: vec![],
+        submessages: vec![],
+        attributes: vec![
+            attr("action", "set_proceeds_split"),
+            attr("recipients", recipients),
+        ],
+        data: None,
+    })
+}
+
+pub fn try_pause(deps: DepsMut, sender: Addr) -> Result<Response, ContractError> {
+    let mut state = STATE.load(deps.storage)?;
+    if state.owner != sender && state.guardian.as_ref() != Some(&sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    state.paused = true;
+    STATE.save(deps.storage, &state)?;
+
+    Ok(Response {
+        messages: vec![],
+        submessages: vec![],
+        attributes: vec![attr("action", "pause"), attr("sender", sender)],
+        data: None,
+    })
+}
+
+pub fn try_unpause(deps: DepsMut, sender: Addr) -> Result<Response, ContractError> {
+    let mut state = STATE.load(deps.storage)?;
+    if state.owner != sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    state.paused = false;
+    STATE.save(deps.storage, &state)?;
+
+    Ok(Response {
+        messages: vec![],
+        submessages: vec![],
+        attributes: vec![attr("action", "unpause")],
+        data: None,
+    })
+}
+
+pub fn try_set_guardian(
+    deps: DepsMut,
+    sender: Addr,
+    guardian: Option<String>,
+) -> Result<Response, ContractError> {
+    let mut state = STATE.load(deps.storage)?;
+    if state.owner != sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    state.guardian = match guardian {
+        Some(guardian) => Some(deps.api.addr_validate(&guardian)?),
+        None => None,
+    };
+    STATE.save(deps.storage, &state)?;
+
+    Ok(Response {
+        messages: vec![],
+        submessages: vec![],
+        attributes: vec![
+            attr("action", "set_guardian"),
+            attr(
+                "guardian",
+                state.guardian.map(String::from).unwrap_or_default(),
+            ),
+        ],
+        data: None,
+    })
+}
+
+pub fn try_set_fee(
+    deps: DepsMut,
+    sender: Addr,
+    bps: u16,
+    collector: Option<String>,
+) -> Result<Response, ContractError> {
+    let state = STATE.load(deps.storage)?;
+    let fee = match state.fee {
+        Some(fee) if fee.collector == sender => fee,
+        _ => return Err(ContractError::Unauthorized {}),
+    };
+    if bps > MAX_BPS {
+        return Err(ContractError::InvalidFee {});
+    }
+
+    let fee = Fee {
+        collector: match collector {
+            Some(collector) => deps.api.addr_validate(&collector)?,
+            None => fee.collector,
+        },
+        bps,
+    };
+    STATE.update(deps.storage, |mut state| -> Result<_, ContractError> {
+        state.fee = Some(fee.clone());
+        Ok(state)
+    })?;
+
+    Ok(Response {
+        messages: vec![],
+        submessages: vec![],
+        attributes: vec![
+            attr("action", "set_fee"),
+            attr("bps", fee.bps),
+            attr("collector", fee.collector),
+        ],
+        data: None,
+    })
+}
+
+pub fn try_set_price(deps: DepsMut, sender: Addr, price: Coin) -> Result<Response, ContractError> {
+    let mut state = STATE.load(deps.storage)?;
+    if state.owner != sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    if price.amount == Uint128(0) {
+        return Err(ContractError::InvalidZeroPrice {});
+    }
+    PRICES.save(deps.storage, &price.denom, &price.amount)?;
+    initialize_if_ready(deps.storage, &mut state)?;
+    STATE.save(deps.storage, &state)?;
+
+    Ok(Response::default())
+}
+
+pub fn try_remove_price(
+    deps: DepsMut,
+    sender: Addr,
+    denom: String,
+) -> Result<Response, ContractError> {
+    if STATE.load(deps.storage)?.owner != sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    if PRICES.may_load(deps.storage, &denom)?.is_none() {
+        return Err(ContractError::PriceNotFound { denom });
+    }
+    PRICES.remove(deps.storage, &denom);
+
+    Ok(Response::default())
+}
+
+pub fn try_set_merkle_root(
+    deps: DepsMut,
+    sender: Addr,
+    merkle_root: Option<String>,
+) -> Result<Response, ContractError> {
+    let state = STATE.load(deps.storage)?;
+    if state.owner != sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    if let Some(root) = &merkle_root {
+        validate_root(root)?;
+        if state.batch_auction.is_some() {
+            return Err(ContractError::InvalidBatchAuction {});
+        }
+        if state.fair_launch.is_some() {
+            return Err(ContractError::InvalidFairLaunch {});
+        }
+    }
+
+    STATE.update(deps.storage, |mut state| -> Result<_, ContractError> {
+        state.merkle_root = merkle_root.clone();
+        Ok(state)
+    })?;
+
+    Ok(Response {
+        messages: vec![],
+        submessages: vec![],
+        attributes: vec![
+            attr("action", "set_merkle_root"),
+            attr("merkle_root", merkle_root.unwrap_or_default()),
+        ],
+        data: None,
+    })
+}
+
+pub fn try_set_cw20_price(
+    deps: DepsMut,
+    sender: Addr,
+    address: String,
+    price: Uint128,
+) -> Result<Response, ContractError> {
+    let mut state = STATE.load(deps.storage)?;
+    if state.owner != sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    if price == Uint128(0) {
+        return Err(ContractError::InvalidZeroPrice {});
+    }
+    let address = deps.api.addr_validate(&address)?;
+    if address == state.cw20_address {
+        return Err(ContractError::InvalidPaymentToken { address });
+    }
+    CW20_PRICES.save(deps.storage, &address, &price)?;
+    initialize_if_ready(deps.storage, &mut state)?;
+    STATE.save(deps.storage, &state)?;
+
+    Ok(Response::default())
+}
+
+pub fn try_remove_cw20_price(
+    deps: DepsMut,
+    sender: Addr,
+    address: String,
+) -> Result<Response, ContractError> {
+    if STATE.load(deps.storage)?.owner != sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    let address = deps.api.addr_validate(&address)?;
+    if CW20_PRICES.may_load(deps.storage, &address)?.is_none() {
+        return Err(ContractError::PriceNotFound {
+            denom: address.into(),
+        });
+    }
+    CW20_PRICES.remove(deps.storage, &address);
+
+    Ok(Response::default())
+}
+
+pub fn try_receive(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    // the cw20 being sold is deposited, an accepted payment cw20 buys
+    let state = STATE.load(deps.storage)?;
+    if state.paused {
+        return Err(ContractError::Paused {});
+    }
+    if state.cw20_address != info.sender {
+        if state.pricing.is_some() || state.soft_cap.is_some() {
+            return Err(ContractError::Cw20PaymentDisabled {});
+        }
+        if CW20_PRICES.may_load(deps.storage, &info.sender)?.is_none() {
+            return Err(ContractError::UnrecognizedToken {
+                address: info.sender,
+            });
+        }
+        return match from_binary(&msg.msg)? {
+            ReceiveMsg::Buy { price } => {
+                let buyer = deps.api.addr_validate(&msg.sender)?;
+                try_buy_with_cw20(deps, env, buyer, info.sender, msg.amount, price, None)
+            }
+            ReceiveMsg::WhitelistBuy {
+                price,
+                allocation,
+                proof,
+            } => {
+                let buyer = deps.api.addr_validate(&msg.sender)?;
+                let allocation = Allocation {
+                    amount: allocation,
+                    proof,
+                };
+                try_buy_with_cw20(
+                    deps,
+                    env,
+                    buyer,
+                    info.sender,
+                    msg.amount,
+                    price,
+                    Some(allocation),
+                )
+            }
+            ReceiveMsg::Receive {} | ReceiveMsg::CreateSale { .. } => {
+                Err(ContractError::InvalidReceiveMsg {})
+            }
+        };
+    }
+
+    // an empty payload or Receive {} is a plain deposit
+    if !msg.msg.is_empty() {
+        match from_binary(&msg.msg)? {
+            ReceiveMsg::Receive {} => {}
+            ReceiveMsg::CreateSale { denom, price } => {
+                return try_create_sale(deps, msg.sender, msg.amount, denom, price)
+            }
+            ReceiveMsg::Buy { .. } | ReceiveMsg::WhitelistBuy { .. } => {
+                return Err(ContractError::InvalidReceiveMsg {})
+            }
+        }
+    }
+
+    let mut state = state;
+    state.balance += msg.amount;
+    initialize_if_ready(deps.storage, &mut state)?;
+    STATE.save(deps.storage, &state)?;
+
+    Ok(Response::default())
+}
+
+/// Funds and prices an uninitialized sale from a single cw20 Send.
+pub fn try_create_sale(
+    deps: DepsMut,
+    sender: String,
+    amount: Uint128,
+    denom: String,
+    price: Uint128,
+) -> Result<Response, ContractError> {
+    let mut state = STATE.load(deps.storage)?;
+    if state.owner != deps.api.addr_validate(&sender)? {
+        return Err(ContractError::Unauthorized {});
+    }
+    if state.status != Status::Uninitialized {
+        return Err(ContractError::SaleInitialized {});
+    }
+    if price == Uint128(0) {
+        return Err(ContractError::InvalidZeroPrice {});
+    }
+    if amount == Uint128(0) {
+        return Err(ContractError::IncorretFunds {});
+    }
+
+    PRICES.save(deps.storage, &denom, &price)?;
+    state.balance += amount;
+    state.status = Status::Initialized;
+    STATE.save(deps.storage, &state)?;
+
+    Ok(Response {
+        messages: vec![],
+        submessages: vec![],
+        attributes: vec![
+            attr("action", "create_sale"),
+            attr("amount", amount),
+            attr("denom", denom),
+            attr("price", price),
+        ],
+        data: None,
+    })
+}
+
+/// Marks the sale initialized once it holds tokens and has a way to price them.
+fn initialize_if_ready(storage: &dyn Storage, state: &mut State) -> StdResult<()> {
+    if state.status == Status::Initialized || state.balance == Uint128(0) {
+        return Ok(());
+    }
+    let priced = state.pricing.is_some()
+        || state.batch_auction.is_some()
+        || state.fair_launch.is_some()
+        || PRICES
+            .range(storage, None, None, Order::Ascending)
+            .next()
+            .is_some()
+        || CW20_PRICES
+            .range(storage, None, None, Order::Ascending)
+            .next()
+            .is_some();
+    if priced {
+        state.status = Status::Initialized;
+    }
+    Ok(())
+}
+
+pub fn try_buy_with_cw20(
+    mut deps: DepsMut,
+    env: Env,
+    buyer: Addr,
+    payment_address: Addr,
+    funds: Uint128,
+    price: Uint128,
+    allocation: Option<Allocation>,
+) -> Result<Response, ContractError> {
+    let mut state = STATE.load(deps.storage)?;
+    if state.live_balance {
+        state = sync_balance(deps.branch(), &env)?;
+    }
+    if state.batch_auction.is_some() {
+        return Err(ContractError::BatchAuctionOnly {});
+    }
+    if state.fair_launch.is_some() {
+        return Err(ContractError::FairLaunchOnly {});
+    }
+    assert_sale_open(&state, &env)?;
+    let allocation = assert_whitelisted(&state, &buyer, allocation)?;
+    let price_current = CW20_PRICES.load(deps.storage, &payment_address)?;
+
+    if price != price_current {
+        return Err(ContractError::PriceNotCurrentError {
+            denom_current: payment_address.to_string(),
+            denom_provided: payment_address.to_string(),
+            price_current,
+            price_provided: price,
+        });
+    }
+
+    let amount = match funds.checked_div(price_current) {
+        Ok(r) => r,
+        Err(_) => return Err(ContractError::DivideByZeroError {}),
+    };
+    if amount == Uint128(0) {
+        return Err(ContractError::IncorretFunds {});
+    }
+    let mut messages = record_purchase(deps.storage, &buyer, amount, allocation)?;
+
+    // only amount * price is kept, the remainder goes back to the buyer
+    let paid = amount.checked_mul(price_current)?;
+    let refunded = funds.checked_sub(paid)?;
+
+    // escrowed proceeds stay in the contract once the fee is paid
+    let (payouts, fee) = match state.escrow_proceeds {
+        true => {
+            let (fee_msgs, fee) = cw20_skim_fee(&state, &payment_address, paid)?;
+            CW20_PROCEEDS.update(deps.storage, &payment_address, |held| -> StdResult<_> {
+                Ok(held
+                    .unwrap_or_default()
+                    .checked_add(paid.checked_sub(fee)?)?)
+            })?;
+            (fee_msgs, fee)
+        }
+        false => cw20_proceeds_msgs(&state, &payment_address, paid)?,
+    };
+    messages.extend(payouts);
+    if refunded != Uint128(0) {
+        messages.push(cw20_transfer_msg(&payment_address, &buyer, refunded)?);
+    }
+
+    let mut attributes = vec![
+        attr("amount", amount),
+        attr("paid", format!("{}{}", paid, payment_address)),
+        attr("refunded", format!("{}{}", refunded, payment_address)),
+    ];
+    if state.fee.is_some() {
+        attributes.push(attr("fee", format!("{}{}", fee, payment_address)));
+    }
+
+    Ok(Response {
+        messages,
+        submessages: vec![],
+        attributes,
+        data: None,
+    })
+}
+
+pub fn try_buy(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    prices: Vec<Coin>,
+    allocation: Option<Allocation>,
+    referrer: Option<String>,
+) -> Result<Response, ContractError> {
+    let mut state = STATE.load(deps.storage).unwrap();
+    if state.paused {
+        return Err(ContractError::Paused {});
+    }
+    if state.live_balance {
+        state = sync_balance(deps.branch(), &env)?;
+    }
+    if state.batch_auction.is_some() {
+        return Err(ContractError::BatchAuctionOnly {});
+    }
+    if state.fair_launch.is_some() {
+        return Err(ContractError::FairLaunchOnly {});
+    }
+    assert_sale_open(&state, &env)?;
+    let allocation = assert_whitelisted(&state, &info.sender, allocation)?;
+    let referrer = match referrer {
+        Some(referrer) => Some(deps.api.addr_validate(&referrer)?),
+        None => None,
+    };
+    if referrer.as_ref() == Some(&info.sender) {
+        return Err(ContractError::SelfReferral {});
+    }
+
+    let (amount, paid, refunded) = match &state.pricing {
+        Some(pricing) => price_dynamic(
+            pricing,
+            env.block.time,
+            state.tokens_sold,
+            &info.funds,
+            &prices,
+        )?,
+        None => price_fixed(deps.storage, &info.funds, &prices)?,
+    };
+    if amount == Uint128(0) {
+        return Err(ContractError::IncorretFunds {});
+    }
+    let mut messages = record_purchase(deps.storage, &info.sender, amount, allocation)?;
+    let proceeds = match &referrer {
+        Some(referrer) => {
+            let (rewards, proceeds) =
+                reward_referrer(deps.storage, &state, referrer, amount, paid.clone())?;
+            messages.extend(rewards);
+            proceeds
+        }
+        None => paid.clone(),
+    };
+
+    // escrowed proceeds stay in the contract, refundable under a soft cap until it is met
+    let mut fee = vec![];
+    if state.soft_cap.is_some() || state.escrow_proceeds {
+        if state.soft_cap.is_some() {
+            for coin in proceeds.iter() {
+                PAYMENTS.update(
+                    deps.storage,
+                    (&info.sender, &coin.denom),
+                    |payment| -> StdResult<_> {
+                        Ok(payment.unwrap_or_default().checked_add(coin.amount)?)
+                    },
+                )?;
+            }
+        }
+        let (fee_msgs, taken) = escrow_proceeds(deps.storage, &state, &proceeds)?;
+        messages.extend(fee_msgs);
+        fee = taken;
+    } else if !proceeds.is_empty() {
+        let (payouts, taken) = proceeds_msgs(&state, &proceeds);
+        messages.extend(payouts);
+        fee = taken;
+    }
+
+    if !refunded.is_empty() {
+        let refund_bank_msg = cosmwasm_std::BankMsg::Send {
+            to_address: info.sender.into(),
+            amount: refunded.clone(),
+        };
+        messages.push(refund_bank_msg.into());
+    }
+
+    let mut attributes = vec![
+        attr("amount", amount),
+        attr("paid", coins_to_string(&paid)),
+        attr("refunded", coins_to_string(&refunded)),
+    ];
+    if state.fee.is_some() {
+        attributes.push(attr("fee", coins_to_string(&fee)));
+    }
+
+    Ok(Response {
+        messages,
+        submessages: vec![],
+        attributes,
+        data: None,
+    })
+}
+
+/// Credits `referrer` with a purchase of `amount` tokens and rewards them under
+/// the referral program, if any. Returns the reward messages and the proceeds
+/// left once a share of the payment is taken.
+fn reward_referrer(
+    storage: &mut dyn Storage,
+    state: &State,
+    referrer: &Addr,
+    amount: Uint128,
+    paid: Vec<Coin>,
+) -> Result<(Vec<CosmosMsg>, Vec<Coin>), ContractError> {
+    REFERRALS.update(storage, referrer, |stats| -> StdResult<_> {
+        let mut stats = stats.unwrap_or_default();
+        stats.referrals += 1;
+        stats.volume = stats.volume.checked_add(amount)?;
+        Ok(stats)
+    })?;
+
+    let referral = match &state.referral {
+        Some(referral) => referral,
+        None => return Ok((vec![], paid)),
+    };
+    match referral.reward {
+        ReferralReward::Proceeds => {
+            let mut reward: Vec<Coin> = vec![];
+            let mut proceeds: Vec<Coin> = vec![];
+            for coin in paid {
+                let share = coin.amount.multiply_ratio(referral.bps, MAX_BPS);
+                if share != Uint128(0) {
+                    reward.push(Coin::new(share.u128(), &coin.denom));
+                }
+                if share != coin.amount {
+                    proceeds.push(Coin::new(coin.amount.u128() - share.u128(), &coin.denom));
+                }
+            }
+            if reward.is_empty() {
+                return Ok((vec![], proceeds));
+            }
+            let reward_bank_msg = BankMsg::Send {
+                to_address: referrer.to_string(),
+                amount: reward,
+            };
+            Ok((vec![reward_bank_msg.into()], proceeds))
+        }
+        ReferralReward::Tokens => {
+            let bonus = amount.multiply_ratio(referral.bps, MAX_BPS);
+            if bonus == Uint128(0) {
+                return Ok((vec![], paid));
+            }
+            // the bonus leaves the sale like a purchase
+            let mut state = STATE.load(storage)?;
+            let tokens_sold = state.tokens_sold.checked_add(bonus)?;
+            if let Some(hard_cap) = state.hard_cap {
+                if tokens_sold > hard_cap {
+                    return Err(ContractError::HardCapExceeded {
+                        remaining: hard_cap.saturating_sub(state.tokens_sold),
+                    });
+                }
+            }
+            state.balance = match state.balance.checked_sub(bonus) {
+                Ok(r) => r,
+                Err(_) => return Err(ContractError::SubtractionError {}),
+            };
+            state.tokens_sold = tokens_sold;
+            STATE.save(storage, &state)?;
+            Ok((deliver_tokens(storage, &state, referrer, bonus)?, paid))
+        }
+    }
+}
+
+/// Prices `funds` at the fixed price of each denom. Returns the tokens bought,
+/// the coins paid for them and the coins to refund.
+fn price_fixed(
+    storage: &dyn Storage,
+    funds: &[Coin],
+    prices: &[Coin],
+) -> Result<(Uint128, Vec<Coin>, Vec<Coin>), ContractError> {
+    let mut amount = Uint128(0);
+    let mut paid: Vec<Coin> = vec![];
+    let mut refunded: Vec<Coin> = vec![];
+
+    for funds in merge_coins(funds) {
+        let price_current = match PRICES.may_load(storage, &funds.denom)? {
+            Some(price) => price,
+            // coins not accepted by the sale go back to the buyer
+            None => {
+                refunded.push(funds);
+                continue;
+            }
+        };
+
+        // the buyer must quote the current price of every denom paid with
+        let price_provided = match prices.iter().find(|p| p.denom == funds.denom) {
+            Some(p) => p.amount,
+            None => {
+                return Err(ContractError::PriceNotProvided { denom: funds.denom });
+            }
+        };
+        if price_provided != price_current {
+            return Err(ContractError::PriceNotCurrentError {
+                denom_current: funds.denom.clone(),
+                denom_provided: funds.denom,
+                price_current,
+                price_provided,
+            });
+        }
+
+        let bought = match funds.amount.checked_div(price_current) {
+            Ok(r) => r,
+            Err(_) => return Err(ContractError::DivideByZeroError {}),
+        };
+        amount = amount.checked_add(bought)?;
+
+        // only bought * price is kept, the remainder goes back to the buyer
+        let cost = bought.checked_mul(price_current)?;
+        let remainder = funds.amount.checked_sub(cost)?;
+        if cost != Uint128(0) {
+            paid.push(Coin {
+                amount: cost,
+                denom: funds.denom.clone(),
+            });
+        }
+        if remainder != Uint128(0) {
+            refunded.push(Coin {
+                amount: remainder,
+                denom: funds.denom,
+            });
+        }
+    }
+
+    Ok((amount, paid, refunded))
+}
+
+/// Prices `funds` under the pricing mode, where only the mode's denom is
+/// accepted and the price provided is the most the buyer pays per token.
+fn price_dynamic(
+    pricing: &Pricing,
+    now: Timestamp,
+    tokens_sold: Uint128,
+    funds: &[Coin],
+    prices: &[Coin],
+) -> Result<(Uint128, Vec<Coin>, Vec<Coin>), ContractError> {
+    let mut amount = Uint128(0);
+    let mut paid: Vec<Coin> = vec![];
+    let mut refunded: Vec<Coin> = vec![];
+
+    for funds in merge_coins(funds) {
+        if funds.denom != pricing.denom() {
+            refunded.push(funds);
+            continue;
+        }
+
+        let price_provided = match prices.iter().find(|p| p.denom == funds.denom) {
+            Some(p) => p.amount,
+            None => {
+                return Err(ContractError::PriceNotProvided { denom: funds.denom });
+            }
+        };
+        let (bought, cost) = pricing.buy(funds.amount, now, tokens_sold)?;
+        if cost > bought.checked_mul(price_provided)? {
+            return Err(ContractError::PriceAboveLimit {
+                price_current: pricing.current_price(now, tokens_sold)?,
+                price_provided,
+            });
+        }
+        amount = bought;
+
+        let remainder = funds.amount.checked_sub(cost)?;
+        if cost != Uint128(0) {
+            paid.push(Coin {
+                amount: cost,
+                denom: funds.denom.clone(),
+            });
+        }
+        if remainder != Uint128(0) {
+            refunded.push(Coin {
+                amount: remainder,
+                denom: funds.denom,
+            });
+        }
+    }
+
+    Ok((amount, paid, refunded))
+}
+
+/// While an allowlist is set only buyers proving an allocation may buy,
+/// returns the allocation capping their purchases.
+fn assert_whitelisted(
+    state: &State,
+    buyer: &Addr,
+    allocation: Option<Allocation>,
+) -> Result<Option<Uint128>, ContractError> {
+    let root = match &state.merkle_root {
+        Some(root) => root,
+        None => return Ok(None),
+    };
+    let allocation = match allocation {
+        Some(allocation) => allocation,
+        None => return Err(ContractError::WhitelistOnly {}),
+    };
+    if !verify_proof(root, leaf_hash(buyer, allocation.amount), &allocation.proof)? {
+        return Err(ContractError::InvalidProof {});
+    }
+    Ok(Some(allocation.amount))
+}
+
+/// Enforces the purchase limits, then records `amount` as sold to `buyer`.
+/// Returns the transfer to the buyer, or nothing when the tokens vest.
+fn record_purchase(
+    storage: &mut dyn Storage,
+    buyer: &Addr,
+    amount: Uint128,
+    allocation: Option<Uint128>,
+) -> Result<Vec<CosmosMsg>, ContractError> {
+    let mut state = STATE.load(storage)?;
+
+    if let Some(min) = state.min_per_purchase {
+        if amount < min {
+            return Err(ContractError::PurchaseBelowMinimum { min });
+        }
+    }
+
+    let tokens_sold = state.tokens_sold.checked_add(amount)?;
+    if let Some(hard_cap) = state.hard_cap {
+        if tokens_sold > hard_cap {
+            return Err(ContractError::HardCapExceeded {
+                remaining: hard_cap.saturating_sub(state.tokens_sold),
+            });
+        }
+    }
+
+    let purchased = PURCHASES.may_load(storage, buyer)?.unwrap_or_default();
+    let updated_purchased = purchased.checked_add(amount)?;
+    if let Some(max) = state.max_per_address {
+        if updated_purchased > max {
+            return Err(ContractError::AddressCapExceeded {
+                remaining: max.saturating_sub(purchased),
+            });
+        }
+    }
+    if let Some(allocation) = allocation {
+        if updated_purchased > allocation {
+            return Err(ContractError::AllocationExceeded {
+                remaining: allocation.saturating_sub(purchased),
+            });
+        }
+    }
+
+    state.balance = match state.balance.checked_sub(amount) {
+        Ok(r) => r,
+        Err(_) => return Err(ContractError::SubtractionError {}),
+    };
+    state.tokens_sold = tokens_sold;
+    STATE.save(storage, &state)?;
+    PURCHASES.save(storage, buyer, &updated_purchased)?;
+
+    deliver_tokens(storage, &state, buyer, amount)
+}
+
+/// Returns the transfer of `amount` to `buyer`, or holds it in the contract
+/// until the soft cap is met.
+fn deliver_tokens(
+    storage: &mut dyn Storage,
+    state: &State,
+    buyer: &Addr,
+    amount: Uint128,
+) -> Result<Vec<CosmosMsg>, ContractError> {
+    if state.soft_cap.is_some() {
+        HELD.update(storage, buyer, |held| -> StdResult<_> {
+            Ok(held.unwrap_or_default().checked_add(amount)?)
+        })?;
+        lock_owed(storage, amount)?;
+        return Ok(vec![]);
+    }
+
+    release_tokens(storage, state, buyer, amount)
+}
+
+/// Returns the transfer of `amount` to `buyer`, or locks it in the contract
+/// until claimed when the sale vests.
+fn release_tokens(
+    storage: &mut dyn Storage,
+    state: &State,
+    buyer: &Addr,
+    amount: Uint128,
+) -> Result<Vec<CosmosMsg>, ContractError> {
+    if state.vesting.is_some() {
+        let mut account = VESTING.may_load(storage, buyer)?.unwrap_or_default();
+        account.total = account.total.checked_add(amount)?;
+        VESTING.save(storage, buyer, &account)?;
+        lock_owed(storage, amount)?;
+        return Ok(vec![]);
+    }
+
+    Ok(vec![cw20_transfer_msg(&state.cw20_address, buyer, amount)?])
+}
+
+/// Records tokens kept in the contract for a buyer, see `OWED`.
+fn lock_owed(storage: &mut dyn Storage, amount: Uint128) -> StdResult<()> {
+    let owed = OWED.may_load(storage)?.unwrap_or_default();
+    OWED.save(storage, &owed.checked_add(amount)?)
+}
+
+/// Releases owed tokens that are sent out or handed back to the owner.
+fn unlock_owed(storage: &mut dyn Storage, amount: Uint128) -> StdResult<()> {
+    let owed = OWED.may_load(storage)?.unwrap_or_default();
+    OWED.save(storage, &owed.checked_sub(amount)?)
+}
+
+fn cw20_transfer_msg(
+    cw20_address: &Addr,
+    recipient: &Addr,
+    amount: Uint128,
+) -> StdResult<CosmosMsg> {
+    let transfer_cw20_msg = Cw20ExecuteMsg::Transfer {
+        recipient: recipient.to_string(),
+        amount,
+    };
+    let exec_cw20_transfer = WasmMsg::Execute {
+        contract_addr: cw20_address.to_string(),
+        msg: to_binary(&transfer_cw20_msg)?,
+        send: vec![],
+    };
+    Ok(exec_cw20_transfer.into())
+}
+
+/// Sums coins of the same denom, keeping the order in which denoms first appear.
+fn merge_coins(coins: &[Coin]) -> Vec<Coin> {
+    let mut merged: Vec<Coin> = vec![];
+    for coin in coins {
+        match merged.iter_mut().find(|c| c.denom == coin.denom) {
+            Some(c) => c.amount += coin.amount,
+            None => merged.push(coin.clone()),
+        }
+    }
+    merged
+}
+
+fn coins_to_string(coins: &[Coin]) -> String {
+    coins
+        .iter()
+        .map(|coin| coin.to_string())
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+pub fn try_claim(deps: DepsMut, env: Env, sender: Addr) -> Result<Response, ContractError> {
+    let state = STATE.load(deps.storage)?;
+    // a fair launch deposit is claimed first, vested tokens are released after
+    if let Some(launch) = &state.fair_launch {
+        let claimed = SETTLEMENTS
+            .may_load(deps.storage, &sender)?
+            .unwrap_or_default()
+            .claimed;
+        if !claimed && DEPOSITS.may_load(deps.storage, &sender)?.is_some() {
+            return try_claim_deposit(deps, env, launch, sender);
+        }
+    }
+    if let Some(amount) = HELD.may_load(deps.storage, &sender)? {
+        if state.phase(&env.block) != Phase::Ended {
+            return Err(ContractError::SaleNotEnded {});
+        }
+        if !state.soft_cap_reached() {
+            return Err(ContractError::SoftCapNotReached {});
+        }
+        HELD.remove(deps.storage, &sender);
+        unlock_owed(deps.storage, amount)?;
+        return Ok(Response {
+            messages: release_tokens(deps.storage, &state, &sender, amount)?,
+            submessages: vec![],
+            attributes: vec![attr("action", "claim"), attr("amount", amount)],
+            data: None,
+        });
+    }
+    let schedule = match state.vesting {
+        Some(schedule) => schedule,
+        None => return Err(ContractError::NothingToClaim {}),
+    };
+
+    let mut account = VESTING.may_load(deps.storage, &sender)?.unwrap_or_default();
+    let claimable = schedule
+        .vested_amount(account.total, env.block.time)?
+        .checked_sub(account.claimed)?;
+    if claimable == Uint128(0) {
+        return Err(ContractError::NothingToClaim {});
+    }
+
+    account.claimed = account.claimed.checked_add(claimable)?;
+    VESTING.save(deps.storage, &sender, &account)?;
+    unlock_owed(deps.storage, claimable)?;
+
+    Ok(Response {
+        messages: vec![cw20_transfer_msg(&state.cw20_address, &sender, claimable)?],
+        submessages: vec![],
+        attributes: vec![attr("action", "claim"), attr("amount", claimable)],
+        data: None,
+    })
+}
+
+pub fn try_deposit(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+    let state = STATE.load(deps.storage)?;
+    if state.paused {
+        return Err(ContractError::Paused {});
+    }
+    let launch = match state.fair_launch.clone() {
+        Some(launch) => launch,
+        None => return Err(ContractError::NotFairLaunch {}),
+    };
+    assert_sale_open(&state, &env)?;
+
+    let funds = merge_coins(&info.funds);
+    let amount = match funds.as_slice() {
+        [coin] if coin.denom == launch.denom && coin.amount != Uint128(0) => coin.amount,
+        _ => return Err(ContractError::IncorretFunds {}),
+    };
+
+    DEPOSITS.update(deps.storage, &info.sender, |deposit| -> StdResult<_> {
+        Ok(deposit.unwrap_or_default().checked_add(amount)?)
+    })?;
+    let mut totals = FAIR_LAUNCH.may_load(deps.storage)?.unwrap_or_default();
+    totals.deposits = totals.deposits.checked_add(amount)?;
+    FAIR_LAUNCH.save(deps.storage, &totals)?;
+
+    Ok(Response {
+        messages: vec![],
+        submessages: vec![],
+        attributes: vec![attr("action", "deposit"), attr("amount", amount)],
+        data: None,
+    })
+}
+
+/// Fixes what a fair launch sold and raised the first time a deposit is
+/// settled after the sale ends, and pays the proceeds to the owner.
+fn close_fair_launch(
+    storage: &mut dyn Storage,
+    env: &Env,
+    launch: &FairLaunch,
+) -> Result<(FairLaunchTotals, Vec<CosmosMsg>), ContractError> {
+    let mut state = STATE.load(storage)?;
+    if state.phase(&env.block) != Phase::Ended {
+        return Err(ContractError::SaleNotEnded {});
+    }
+    let mut totals = FAIR_LAUNCH.may_load(storage)?.unwrap_or_default();
+    if totals.sold.is_some() {
+        return Ok((totals, vec![]));
+    }
+
+    // oversubscribed sales sell the whole supply, others what the deposits cover
+    let supply = match state.hard_cap {
+        Some(hard_cap) => hard_cap.min(state.balance),
+        None => state.balance,
+    };
+    let covered = match totals.deposits.checked_div(launch.price) {
+        Ok(r) => r,
+        Err(_) => return Err(ContractError::DivideByZeroError {}),
+    };
+    let sold = supply.min(covered);
+    totals.sold = Some(sold);
+    totals.raised = sold.checked_mul(launch.price)?;
+    FAIR_LAUNCH.save(storage, &totals)?;
+
+    state.balance = match state.balance.checked_sub(sold) {
+        Ok(r) => r,
+        Err(_) => return Err(ContractError::SubtractionError {}),
+    };
+    state.tokens_sold = state.tokens_sold.checked_add(sold)?;
+    STATE.save(storage, &state)?;
+    lock_owed(storage, sold)?;
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    if totals.raised != Uint128(0) {
+        let raised = coins(totals.raised.u128(), &launch.denom);
+        if state.escrow_proceeds {
+            messages = escrow_proceeds(storage, &state, &raised)?.0;
+        } else {
+            messages = proceeds_msgs(&state, &raised).0;
+        }
+    }
+    Ok((totals, messages))
+}
+
+fn try_claim_deposit(
+    deps: DepsMut,
+    env: Env,
+    launch: &FairLaunch,
+    sender: Addr,
+) -> Result<Response, ContractError> {
+    let (mut totals, mut messages) = close_fair_launch(deps.storage, &env, launch)?;
+    let deposit = DEPOSITS.load(deps.storage, &sender)?;
+    let amount = totals.claim(deposit)?;
+    FAIR_LAUNCH.save(deps.storage, &totals)?;
+    SETTLEMENTS.update(deps.storage, &sender, |settlement| -> StdResult<_> {
+        let mut settlement = settlement.unwrap_or_default();
+        settlement.claimed = true;
+        Ok(settlement)
+    })?;
+
+    if amount != Uint128(0) {
+        PURCHASES.update(deps.storage, &sender, |purchased| -> StdResult<_> {
+            Ok(purchased.unwrap_or_default().checked_add(amount)?)
+        })?;
+        let state = STATE.load(deps.storage)?;
+        unlock_owed(deps.storage, amount)?;
+        messages.extend(deliver_tokens(deps.storage, &state, &sender, amount)?);
+    }
+
+    Ok(Response {
+        messages,
+        submessages: vec![],
+        attributes: vec![attr("action", "claim"), attr("amount", amount)],
+        data: None,
+    })
+}
+
+pub fn try_refund(deps: DepsMut, env: Env, sender: Addr) -> Result<Response, ContractError> {
+    let state = STATE.load(deps.storage)?;
+    if state.soft_cap.is_some() {
+        return try_refund_payments(deps, env, state, sender);
+    }
+    let launch = match state.fair_launch {
+        Some(launch) => launch,
+        None => return Err(ContractError::NothingToRefund {}),
+    };
+    let mut settlement = SETTLEMENTS
+        .may_load(deps.storage, &sender)?
+        .unwrap_or_default();
+    let deposit = match DEPOSITS.may_load(deps.storage, &sender)? {
+        Some(deposit) if !settlement.refunded => deposit,
+        _ => return Err(ContractError::NothingToRefund {}),
+    };
+
+    let (mut totals, mut messages) = close_fair_launch(deps.storage, &env, &launch)?;
+    let refund = totals.refund(deposit)?;
+    FAIR_LAUNCH.save(deps.storage, &totals)?;
+    settlement.refunded = true;
+    SETTLEMENTS.save(deps.storage, &sender, &settlement)?;
+
+    if refund != Uint128(0) {
+        let transfer_bank_msg = BankMsg::Send {
+            to_address: sender.into(),
+            amount: coins(refund.u128(), &launch.denom),
+        };
+        messages.push(transfer_bank_msg.into());
+    }
+
+    Ok(Response {
+        messages,
+        submessages: vec![],
+        attributes: vec![
+            attr("action", "refund"),
+            attr("refunded", format!("{}{}", refund, launch.denom)),
+        ],
+        data: None,
+    })
+}
+
+/// Returns everything `sender` paid once the sale ends short of its soft cap.
+/// Their held tokens go back to the owner with the rest of the sale.
+fn try_refund_payments(
+    deps: DepsMut,
+    env: Env,
+    state: State,
+    sender: Addr,
+) -> Result<Response, ContractError> {
+    if state.phase(&env.block) != Phase::Ended {
+        return Err(ContractError::SaleNotEnded {});
+    }
+    if state.soft_cap_reached() {
+        return Err(ContractError::SoftCapReached {});
+    }
+
+    let payments = PAYMENTS
+        .prefix(&sender)
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            let (denom, amount) = item?;
+            Ok(Coin {
+                denom: String::from_utf8(denom)?,
+                amount,
+            })
+        })
+        .collect::<StdResult<Vec<Coin>>>()?;
+    if payments.is_empty() {
+        return Err(ContractError::NothingToRefund {});
+    }
+    // the fee was set aside from each payment, whatever the proceeds no longer
+    // cover comes back out of the fees
+    for payment in payments.iter() {
+        PAYMENTS.remove(deps.storage, (&sender, &payment.denom));
+        let proceeds = PROCEEDS
+            .may_load(deps.storage, &payment.denom)?
+            .unwrap_or_default();
+        let from_fees = payment.amount.saturating_sub(proceeds);
+        PROCEEDS.save(
+            deps.storage,
+            &payment.denom,
+            &proceeds.saturating_sub(payment.amount),
+        )?;
+        if from_fees != Uint128(0) {
+            let fees = FEES.load(deps.storage, &payment.denom)?;
+            FEES.save(deps.storage, &payment.denom, &fees.checked_sub(from_fees)?)?;
+        }
+    }
+    // the tokens held for the buyer go back on sale
+    if let Some(held) = HELD.may_load(deps.storage, &sender)? {
+        HELD.remove(deps.storage, &sender);
+        unlock_owed(deps.storage, held)?;
+        let mut state = state;
+        state.balance = state.balance.checked_add(held)?;
+        state.tokens_sold = state.tokens_sold.checked_sub(held)?;
+        STATE.save(deps.storage, &state)?;
+    }
+
+    let refund_bank_msg = BankMsg::Send {
+        to_address: sender.into(),
+        amount: payments.clone(),
+    };
+
+    Ok(Response {
+        messages: vec![refund_bank_msg.into()],
+        submessages: vec![],
+        attributes: vec![
+            attr("action", "refund"),
+            attr("refunded", coins_to_string(&payments)),
+        ],
+        data: None,
+    })
+}
+
+pub fn try_bid(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    amount: Uint128,
+    max_price: Uint128,
+) -> Result<Response, ContractError> {
+    let state = STATE.load(deps.storage)?;
+    if state.paused {
+        return Err(ContractError::Paused {});
+    }
+    let auction = match state.batch_auction.clone() {
+        Some(auction) => auction,
+        None => return Err(ContractError::NotBatchAuction {}),
+    };
+    assert_sale_open(&state, &env)?;
+
+    if max_price < auction.reserve_price {
+        return Err(ContractError::BidBelowReserve {
+            reserve_price: auction.reserve_price,
+        });
+    }
+    if amount == Uint128(0) {
+        return Err(ContractError::IncorretFunds {});
+    }
+    if let Some(min) = state.min_per_purchase {
+        if amount < min {
+            return Err(ContractError::PurchaseBelowMinimum { min });
+        }
+    }
+    if let Some(max) = state.max_per_address {
+        if amount > max {
+            return Err(ContractError::AddressCapExceeded { remaining: max });
+        }
+    }
+
+    let bid = Bid { amount, max_price };
+    let mut sent = Uint128(0);
+    let mut refunded: Vec<Coin> = vec![];
+    for funds in merge_coins(&info.funds) {
+        if funds.denom == auction.denom {
+            sent = funds.amount;
+        } else {
+            refunded.push(funds);
+        }
+    }
+    let mut refund = match sent.checked_sub(bid.escrow()?) {
+        Ok(r) => r,
+        Err(_) => return Err(ContractError::IncorretFunds {}),
+    };
+
+    // a new bid replaces the previous one, whose escrow goes back
+    if let Some(previous) = BIDS.may_load(deps.storage, &info.sender)? {
+        refund = refund.checked_add(previous.escrow()?)?;
+        let key = U128Key::new(previous.max_price.u128());
+        let demand = DEMAND
+            .load(deps.storage, key.clone())?
+            .checked_sub(previous.amount)?;
+        if demand == Uint128(0) {
+            DEMAND.remove(deps.storage, key);
+        } else {
+            DEMAND.save(deps.storage, key, &demand)?;
+        }
+    }
+    BIDS.save(deps.storage, &info.sender, &bid)?;
+    DEMAND.update(
+        deps.storage,
+        U128Key::new(max_price.u128()),
+        |demand| -> StdResult<_> { Ok(demand.unwrap_or_default().checked_add(amount)?) },
+    )?;
+
+    if refund != Uint128(0) {
+        refunded.push(Coin {
+            denom: auction.denom,
+            amount: refund,
+        });
+    }
+    let mut messages: Vec<CosmosMsg> = vec![];
+    if !refunded.is_empty() {
+        let refund_bank_msg = BankMsg::Send {
+            to_address: info.sender.into(),
+            amount: refunded.clone(),
+        };
+        messages.push(refund_bank_msg.into());
+    }
+
+    Ok(Response {
+        messages,
+        submessages: vec![],
+        attributes: vec![
+            attr("action", "bid"),
+            attr("amount", amount),
+            attr("max_price", max_price),
+            attr("refunded", coins_to_string(&refunded)),
+        ],
+        data: None,
+    })
+}
+
+/// Walks up to `limit` price levels from the highest bid down, until demand
+/// covers the supply or every bid is counted, then fixes the clearing price
+/// and pays the proceeds to the owner.
+pub fn try_finalize(
+    deps: DepsMut,
+    env: Env,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let mut state = STATE.load(deps.storage)?;
+    let auction = match state.batch_auction.clone() {
+        Some(auction) => auction,
+        None => return Err(ContractError::NotBatchAuction {}),
+    };
+    if state.phase(&env.block) != Phase::Ended {
+        return Err(ContractError::SaleNotEnded {});
+    }
+
+    let mut clearing = match CLEARING.may_load(deps.storage)? {
+        Some(clearing) => clearing,
+        None => Clearing {
+            supply: match state.hard_cap {
+                Some(hard_cap) => hard_cap.min(state.balance),
+                None => state.balance,
+            },
+            ..Clearing::default()
+        },
+    };
+    if clearing.price.is_some() {
+        return Err(ContractError::AuctionFinalized {});
+    }
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let min = Some(Bound::inclusive_int(auction.reserve_price.u128()));
+    let max = clearing
+        .cursor
+        .map(|price| Bound::exclusive_int(price.u128()));
+    let levels = DEMAND
+        .range(deps.storage, min, max, Order::Descending)
+        .take(limit)
+        .map(|item| {
+            let (price, demand) = item?;
+            Ok((parse_price_key(&price)?, demand))
+        })
+        .collect::<StdResult<Vec<(Uint128, Uint128)>>>()?;
+
+    let walked = levels.len();
+    for (price, demand) in levels {
+        let total = clearing.filled.checked_add(demand)?;
+        if total >= clearing.supply {
+            clearing.price = Some(price);
+            clearing.marginal_demand = demand;
+            clearing.marginal_supply = clearing.supply.checked_sub(clearing.filled)?;
+            break;
+        }
+        clearing.filled = total;
+        clearing.cursor = Some(price);
+    }
+
+    // every bid fits in the supply, the lowest one sets the price
+    if clearing.price.is_none() && walked < limit {
+        match clearing.cursor {
+            Some(price) => {
+                let demand = DEMAND.load(deps.storage, U128Key::new(price.u128()))?;
+                clearing.filled = clearing.filled.checked_sub(demand)?;
+                clearing.marginal_demand = demand;
+                clearing.marginal_supply = demand;
+                clearing.price = Some(price);
+            }
+            None => clearing.price = Some(auction.reserve_price),
+        }
+    }
+    CLEARING.save(deps.storage, &clearing)?;
+
+    let price = match clearing.price {
+        Some(price) => price,
+        None => {
+            return Ok(Response {
+                messages: vec![],
+                submessages: vec![],
+                attributes: vec![attr("action", "finalize"), attr("levels", walked)],
+                data: None,
+            })
+        }
+    };
+
+    let sold = clearing.sold();
+    state.balance = match state.balance.checked_sub(sold) {
+        Ok(r) => r,
+        Err(_) => return Err(ContractError::SubtractionError {}),
+    };
+    state.tokens_sold = state.tokens_sold.checked_add(sold)?;
+    STATE.save(deps.storage, &state)?;
+    lock_owed(deps.storage, sold)?;
+
+    let proceeds = sold.checked_mul(price)?;
+    let (messages, fee) = match proceeds {
+        Uint128(0) => (vec![], vec![]),
+        _ if state.escrow_proceeds => {
+            escrow_proceeds(deps.storage, &state, &coins(proceeds.u128(), auction.denom))?
+        }
+        _ => proceeds_msgs(&state, &coins(proceeds.u128(), auction.denom)),
+    };
+
+    let mut attributes = vec![
+        attr("action", "finalize"),
+        attr("levels", walked),
+        attr("clearing_price", price),
+        attr("sold", sold),
+    ];
+    if state.fee.is_some() {
+        attributes.push(attr("fee", coins_to_string(&fee)));
+    }
+
+    Ok(Response {
+        messages,
+        submessages: vec![],
+        attributes,
+        data: None,
+    })
+}
+
+fn parse_price_key(key: &[u8]) -> StdResult<Uint128> {
+    let mut price = [0u8; 16];
+    if key.len() != price.len() {
+        return Err(StdError::generic_err("Invalid price key"));
+    }
+    price.copy_from_slice(key);
+    Ok(Uint128(u128::from_be_bytes(price)))
+}
+
+pub fn try_settle_bid(deps: DepsMut, sender: Addr) -> Result<Response, ContractError> {
+    let state = STATE.load(deps.storage)?;
+    let mut clearing = CLEARING.may_load(deps.storage)?.unwrap_or_default();
+    let (auction, price) = match (state.batch_auction.clone(), clearing.price) {
+        (Some(auction), Some(price)) => (auction, price),
+        _ => return Err(ContractError::AuctionNotFinalized {}),
+    };
+    let bid = match BIDS.may_load(deps.storage, &sender)? {
+        Some(bid) => bid,
+        None => return Err(ContractError::NoBid {}),
+    };
+
+    let amount = clearing.allocate(&bid)?;
+    CLEARING.save(deps.storage, &clearing)?;
+    BIDS.remove(deps.storage, &sender);
+    let refund = bid.escrow()?.checked_sub(amount.checked_mul(price)?)?;
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    if amount != Uint128(0) {
+        let purchased = PURCHASES
+            .may_load(deps.storage, &sender)?
+            .unwrap_or_default();
+        PURCHASES.save(deps.storage, &sender, &purchased.checked_add(amount)?)?;
+        unlock_owed(deps.storage, amount)?;
+        messages = deliver_tokens(deps.storage, &state, &sender, amount)?;
+    }
+    if refund != Uint128(0) {
+        let refund_bank_msg = BankMsg::Send {
+            to_address: sender.into(),
+            amount: coins(refund.u128(), auction.denom.clone()),
+        };
+        messages.push(refund_bank_msg.into());
+    }
+
+    Ok(Response {
+        messages,
+        submessages: vec![],
+        attributes: vec![
+            attr("action", "settle_bid"),
+            attr("amount", amount),
+            attr("refunded", format!("{}{}", refund, auction.denom)),
+        ],
+        data: None,
+    })
+}
+
+/// Checks the owner can withdraw unsold tokens and returns the state to withdraw
+/// from, along with the proceeds paid out when this closes a fair launch.
+fn prepare_withdrawal(
+    mut deps: DepsMut,
+    env: &Env,
+    sender: &Addr,
+) -> Result<(State, Vec<CosmosMsg>), ContractError> {
+    let mut state = STATE.load(deps.storage)?;
+
+    if state.owner != *sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    if state.live_balance {
+        state = sync_balance(deps.branch(), env)?;
+    }
+    // only what a fair launch did not sell can be withdrawn
+    let (state, messages) = match &state.fair_launch {
+        Some(launch) => {
+            let messages = close_fair_launch(deps.storage, env, launch)?.1;
+            (STATE.load(deps.storage)?, messages)
+        }
+        None => (state, vec![]),
+    };
+    // the tokens bid for stay until the auction is finalized
+    if state.batch_auction.is_some() {
+        let clearing = CLEARING.may_load(deps.storage)?.unwrap_or_default();
+        if clearing.price.is_none() {
+            return Err(ContractError::AuctionNotFinalized {});
+        }
+    }
+    Ok((state, messages))
+}
+
+pub fn try_withdraw(
+    mut deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    amount: Uint128,
+    recipient: Option<String>,
+    msg: Option<Binary>,
+) -> Result<Response, ContractError> {
+    if amount == Uint128(0) {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+    let (mut state, mut messages) = prepare_withdrawal(deps.branch(), &env, &sender)?;
+    let recipient = match recipient {
+        Some(recipient) => deps.api.addr_validate(&recipient)?,
+        None => state.owner.clone(),
+    };
+
+    state.balance = match state.balance.checked_sub(amount) {
+        Ok(r) => r,
+        Err(_) => {
+            return Err(ContractError::InsufficientBalance {
+                available: state.balance,
+            })
+        }
+    };
+    STATE.save(deps.storage, &state)?;
+
+    // a payload hands the tokens to a contract through its receive hook
+    let withdraw_msg = match msg {
+        Some(msg) => WasmMsg::Execute {
+            contract_addr: state.cw20_address.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Send {
+                contract: recipient.to_string(),
+                amount,
+                msg: Some(msg),
+            })?,
+            send: vec![],
+        }
+        .into(),
+        None => cw20_transfer_msg(&state.cw20_address, &recipient, amount)?,
+    };
+    messages.push(withdraw_msg);
+
+    Ok(Response {
+        messages,
+        submessages: vec![],
+        attributes: vec![
+            attr("action", "withdraw"),
+            attr("amount", amount),
+            attr("recipient", recipient),
+        ],
+        data: None,
+    })
+}
+
+pub fn try_withdraw_all(
+    mut deps: DepsMut,
+    env: Env,
+    sender: Addr,
+) -> Result<Response, ContractError> {
+    let (state, mut messages) = prepare_withdrawal(deps.branch(), &env, &sender)?;
+
+    // once a soft capped sale ends, escrowed proceeds go to the owner if it
+    // succeeded, otherwise the held tokens come back with the balance
+    let mut amount = state.balance;
+    let mut tokens_sold = state.tokens_sold;
+    if state.soft_cap.is_some() && state.phase(&env.block) == Phase::Ended {
+        if state.soft_cap_reached() {
+            let proceeds = take_proceeds(deps.storage)?;
+            messages.extend(release_fees(deps.storage, &state)?.0);
+            messages.extend(payout_msgs(&state, proceeds));
+        } else {
+            amount = amount.checked_add(tokens_sold)?;
+            unlock_owed(deps.storage, tokens_sold)?;
+            tokens_sold = Uint128(0);
+        }
+    }
+
+    let cw20_transfer_cosmos_msg = cw20_transfer_msg(&state.cw20_address, &state.owner, amount)?;
+    messages.push(cw20_transfer_cosmos_msg);
+
+    STATE.update(deps.storage, |mut state| -> Result<_, ContractError> {
+        state.balance = Uint128(0);
+        state.tokens_sold = tokens_sold;
+        Ok(state)
+    })?;
+
+    Ok(Response {
+        messages,
+        submessages: vec![],
+        attributes: vec![attr("amount", amount)],
+        data: None,
+    })
+}
+
+pub fn try_sync(deps: DepsMut, env: Env, sender: Addr) -> Result<Response, ContractError> {
+    let previous = STATE.load(deps.storage)?;
+    if previous.owner != sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    let state = sync_balance(deps, &env)?;
+
+    Ok(Response {
+        messages: vec![],
+        submessages: vec![],
+        attributes: vec![
+            attr("action", "sync"),
+            attr("previous_balance", previous.balance),
+            attr("balance", state.balance),
+        ],
+        data: None,
+    })
+}
+
+/// Sets the balance to the live cw20 balance less the tokens owed to buyers,
+/// picking up tokens transferred without the receive hook.
+fn sync_balance(deps: DepsMut, env: &Env) -> Result<State, ContractError> {
+    let mut state = STATE.load(deps.storage)?;
+    let cw20_balance = query_cw20_balance(deps.as_ref(), env, &state)?;
+    let owed = OWED.may_load(deps.storage)?.unwrap_or_default();
+    state.balance = cw20_balance.saturating_sub(owed);
+    initialize_if_ready(deps.storage, &mut state)?;
+    STATE.save(deps.storage, &state)?;
+    Ok(state)
+}
+
+fn query_cw20_balance(deps: Deps, env: &Env, state: &State) -> StdResult<Uint128> {
+    let res: BalanceResponse = deps.querier.query_wasm_smart(
+        &state.cw20_address,
+        &Cw20QueryMsg::Balance {
+            address: env.contract.address.to_string(),
+        },
+    )?;
+    Ok(res.balance)
+}
+
+pub fn try_withdraw_proceeds(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    denom: String,
+    amount: Option<Uint128>,
+    recipient: Option<String>,
+) -> Result<Response, ContractError> {
+    let state = STATE.load(deps.storage)?;
+    if state.owner != sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    let payment_address = Addr::unchecked(&denom);
+    if let Some(available) = CW20_PROCEEDS.may_load(deps.storage, &payment_address)? {
+        return withdraw_cw20_proceeds(deps, &state, payment_address, available, amount, recipient);
+    }
+    // escrowed payments may still be refunded until the soft cap is met
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut fee: Vec<Coin> = vec![];
+    if state.soft_cap.is_some() {
+        if state.phase(&env.block) != Phase::Ended {
+            return Err(ContractError::SaleNotEnded {});
+        }
+        if !state.soft_cap_reached() {
+            return Err(ContractError::SoftCapNotReached {});
+        }
+        let (fee_msgs, released) = release_fees(deps.storage, &state)?;
+        messages = fee_msgs;
+        fee = released;
+    }
+
+    let available = PROCEEDS.may_load(deps.storage, &denom)?.unwrap_or_default();
+    let amount = amount.unwrap_or(available);
+    if amount == Uint128(0) || amount > available {
+        return Err(ContractError::InsufficientProceeds { available });
+    }
+    PROCEEDS.save(deps.storage, &denom, &available.checked_sub(amount)?)?;
+
+    let proceeds = coins(amount.u128(), &denom);
+    match recipient {
+        Some(recipient) => {
+            let transfer_bank_msg = BankMsg::Send {
+                to_address: deps.api.addr_validate(&recipient)?.into(),
+                amount: proceeds,
+            };
+            messages.push(transfer_bank_msg.into());
+        }
+        None => messages.extend(payout_msgs(&state, proceeds)),
+    }
+
+    let mut attributes = vec![
+        attr("action", "withdraw_proceeds"),
+        attr("amount", format!("{}{}", amount, denom)),
+    ];
+    if state.fee.is_some() {
+        attributes.push(attr("fee", coins_to_string(&fee)));
+    }
+
+    Ok(Response {
+        messages,
+        submessages: vec![],
+        attributes,
+        data: None,
+    })
+}
+
+fn withdraw_cw20_proceeds(
+    deps: DepsMut,
+    state: &State,
+    payment_address: Addr,
+    available: Uint128,
+    amount: Option<Uint128>,
+    recipient: Option<String>,
+) -> Result<Response, ContractError> {
+    let amount = amount.unwrap_or(available);
+    if amount == Uint128(0) || amount > available {
+        return Err(ContractError::InsufficientProceeds { available });
+    }
+    CW20_PROCEEDS.save(
+        deps.storage,
+        &payment_address,
+        &available.checked_sub(amount)?,
+    )?;
+
+    let messages = match recipient {
+        Some(recipient) => {
+            let recipient = deps.api.addr_validate(&recipient)?;
+            vec![cw20_transfer_msg(&payment_address, &recipient, amount)?]
+        }
+        None => cw20_payout_msgs(state, &payment_address, amount)?,
+    };
+
+    Ok(Response {
+        messages,
+        submessages: vec![],
+        attributes: vec![
+            attr("action", "withdraw_proceeds"),
+            attr("amount", format!("{}{}", amount, payment_address)),
+        ],
+        data: None,
+    })
+}
+
+pub fn try_release_fees(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let state = STATE.load(deps.storage)?;
+    if state.soft_cap.is_none() {
+        return Err(ContractError::NothingToClaim {});
+    }
+    if state.phase(&env.block) != Phase::Ended {
+        return Err(ContractError::SaleNotEnded {});
+    }
+    if !state.soft_cap_reached() {
+        return Err(ContractError::SoftCapNotReached {});
+    }
+
+    let (messages, fee) = release_fees(deps.storage, &state)?;
+    if fee.is_empty() {
+        return Err(ContractError::NothingToClaim {});
+    }
+
+    Ok(Response {
+        messages,
+        submessages: vec![],
+        attributes: vec![
+            attr("action", "release_fees"),
+            attr("fee", coins_to_string(&fee)),
+        ],
+        data: None,
+    })
+}
+
+/// Takes the platform fee off `proceeds`. Returns the transfer to the fee
+/// collector, the fee and the proceeds left.
+fn skim_fee(state: &State, proceeds: &[Coin]) -> (Vec<CosmosMsg>, Vec<Coin>, Vec<Coin>) {
+    let mut fee: Vec<Coin> = vec![];
+    let mut rest: Vec<Coin> = vec![];
+    for coin in proceeds {
+        let taken = state
+            .fee
+            .as_ref()
+            .map_or(Uint128(0), |fee| fee.amount(coin.amount));
+        if taken != Uint128(0) {
+            fee.push(Coin::new(taken.u128(), &coin.denom));
+        }
+        if taken != coin.amount {
+            rest.push(Coin::new(coin.amount.u128() - taken.u128(), &coin.denom));
+        }
+    }
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    if let (Some(config), false) = (state.fee.as_ref(), fee.is_empty()) {
+        let fee_bank_msg = BankMsg::Send {
+            to_address: config.collector.to_string(),
+            amount: fee.clone(),
+        };
+        messages.push(fee_bank_msg.into());
+    }
+    (messages, fee, rest)
+}
+
+/// Pays `proceeds` to the owner, or shares every coin between the recipients
+/// of the proceeds split, once the platform fee is taken. Returns the fee too.
+fn proceeds_msgs(state: &State, proceeds: &[Coin]) -> (Vec<CosmosMsg>, Vec<Coin>) {
+    let (mut messages, fee, proceeds) = skim_fee(state, proceeds);
+    messages.extend(payout_msgs(state, proceeds));
+    (messages, fee)
+}
+
+/// Holds `proceeds` in the contract once the platform fee is taken. The fee is
+/// paid straight away, or set aside while payments are refundable under a soft
+/// cap. Returns the fee messages and the fee.
+fn escrow_proceeds(
+    storage: &mut dyn Storage,
+    state: &State,
+    proceeds: &[Coin],
+) -> StdResult<(Vec<CosmosMsg>, Vec<Coin>)> {
+    let (messages, fee, rest) = skim_fee(state, proceeds);
+    credit(storage, &PROCEEDS, &rest)?;
+    if state.soft_cap.is_none() {
+        return Ok((messages, fee));
+    }
+    credit(storage, &FEES, &fee)?;
+    Ok((vec![], fee))
+}
+
+/// Pays every fee set aside under the soft cap to the collector.
+fn release_fees(
+    storage: &mut dyn Storage,
+    state: &State,
+) -> StdResult<(Vec<CosmosMsg>, Vec<Coin>)> {
+    let fee = FEES
+        .range(storage, None, None, Order::Ascending)
+        .map(|item| {
+            let (denom, amount) = item?;
+            Ok(Coin {
+                denom: String::from_utf8(denom)?,
+                amount,
+            })
+        })
+        .filter(|coin| !matches!(coin, Ok(coin) if coin.amount == Uint128(0)))
+        .collect::<StdResult<Vec<Coin>>>()?;
+    for coin in fee.iter() {
+        FEES.remove(storage, &coin.denom);
+    }
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    if let (Some(config), false) = (state.fee.as_ref(), fee.is_empty()) {
+        let fee_bank_msg = BankMsg::Send {
+            to_address: config.collector.to_string(),
+            amount: fee.clone(),
+        };
+        messages.push(fee_bank_msg.into());
+    }
+    Ok((messages, fee))
+}
+
+/// Pays `proceeds` to the owner, or shares every coin between the recipients
+/// of the proceeds split.
+fn payout_msgs(state: &State, proceeds: Vec<Coin>) -> Vec<CosmosMsg> {
+    if proceeds.is_empty() {
+        return vec![];
+    }
+    let split = match &state.split {
+        Some(split) => split,
+        None => {
+            let transfer_bank_msg = BankMsg::Send {
+                to_address: state.owner.to_string(),
+                amount: proceeds,
+            };
+            return vec![transfer_bank_msg.into()];
+        }
+    };
+
+    let mut payouts: Vec<(Addr, Vec<Coin>)> = vec![];
+    for coin in proceeds.iter() {
+        for (address, amount) in split.split(coin.amount) {
+            let share = Coin {
+                denom: coin.denom.clone(),
+                amount,
+            };
+            match payouts.iter_mut().find(|(payee, _)| *payee == address) {
+                Some((_, shares)) => shares.push(share),
+                None => payouts.push((address, vec![share])),
+            }
+        }
+    }
+    payouts
+        .into_iter()
+        .map(|(address, amount)| {
+            CosmosMsg::from(BankMsg::Send {
+                to_address: address.into(),
+                amount,
+            })
+        })
+        .collect()
+}
+
+/// Pays `amount` of the payment cw20 to the owner, or shares it between the
+/// recipients of the proceeds split, once the platform fee is taken. Returns
+/// the fee too.
+fn cw20_proceeds_msgs(
+    state: &State,
+    payment_address: &Addr,
+    amount: Uint128,
+) -> StdResult<(Vec<CosmosMsg>, Uint128)> {
+    let (mut messages, fee) = cw20_skim_fee(state, payment_address, amount)?;
+    messages.extend(cw20_payout_msgs(
+        state,
+        payment_address,
+        amount.checked_sub(fee)?,
+    )?);
+    Ok((messages, fee))
+}
+
+/// Takes the platform fee off `amount` of the payment cw20. Returns the
+/// transfer to the fee collector and the fee.
+fn cw20_skim_fee(
+    state: &State,
+    payment_address: &Addr,
+    amount: Uint128,
+) -> StdResult<(Vec<CosmosMsg>, Uint128)> {
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let fee = state
+        .fee
+        .as_ref()
+        .map_or(Uint128(0), |fee| fee.amount(amount));
+    if let (Some(config), false) = (state.fee.as_ref(), fee == Uint128(0)) {
+        messages.push(cw20_transfer_msg(payment_address, &config.collector, fee)?);
+    }
+    Ok((messages, fee))
+}
+
+/// Pays `amount` of the payment cw20 to the owner, or shares it between the
+/// recipients of the proceeds split.
+fn cw20_payout_msgs(
+    state: &State,
+    payment_address: &Addr,
+    amount: Uint128,
+) -> StdResult<Vec<CosmosMsg>> {
+    let mut messages: Vec<CosmosMsg> = vec![];
+    if amount == Uint128(0) {
+        return Ok(messages);
+    }
+    match &state.split {
+        Some(split) => {
+            for (address, amount) in split.split(amount) {
+                messages.push(cw20_transfer_msg(payment_address, &address, amount)?);
+            }
+        }
+        None => messages.push(cw20_transfer_msg(payment_address, &state.owner, amount)?),
+    }
+    Ok(messages)
+}
+
+/// Adds `amount` to the per denom `balances` held by the contract.
+fn credit(
+    storage: &mut dyn Storage,
+    balances: &Map<&str, Uint128>,
+    amount: &[Coin],
+) -> StdResult<()> {
+    for coin in amount {
+        balances.update(storage, &coin.denom, |held| -> StdResult<_> {
+            Ok(held.unwrap_or_default().checked_add(coin.amount)?)
+        })?;
+    }
+    Ok(())
+}
+
+/// Every non-zero proceeds balance held by the contract.
+fn list_proceeds(storage: &dyn Storage) -> StdResult<Vec<Coin>> {
+    PROCEEDS
+        .range(storage, None, None, Order::Ascending)
+        .map(|item| {
+            let (denom, amount) = item?;
+            Ok(Coin {
+                denom: String::from_utf8(denom)?,
+                amount,
+            })
+        })
+        .filter(|coin| !matches!(coin, Ok(coin) if coin.amount == Uint128(0)))
+        .collect()
+}
+
+/// Removes and returns every non-zero proceeds balance held by the contract.
+fn take_proceeds(storage: &mut dyn Storage) -> StdResult<Vec<Coin>> {
+    let proceeds = list_proceeds(storage)?;
+    for coin in proceeds.iter() {
+        PROCEEDS.remove(storage, &coin.denom);
+    }
+    Ok(proceeds)
+}
+
+pub fn try_propose_new_owner(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    owner: String,
+    expiry: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    if STATE.load(deps.storage)?.owner != sender {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if let Some(expiry) = expiry {
+        if expiry.is_expired(&env.block) {
+            return Err(ContractError::OwnershipProposalExpired {});
+        }
+    }
+
+    let pending = PendingOwner {
+        owner: deps.api.addr_validate(&owner)?,
+        expiry,
+    };
+    PENDING_OWNER.save(deps.storage, &pending)?;
+
+    Ok(Response {
+        messages: vec![],
+        submessages: vec![],
+        attributes: vec![
+            attr("action", "propose_new_owner"),
+            attr("pending_owner", pending.owner),
+        ],
+        data: None,
+    })
+}
+
+pub fn try_accept_ownership(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+) -> Result<Response, ContractError> {
+    let pending = match PENDING_OWNER.may_load(deps.storage)? {
+        Some(pending) => pending,
+        None => return Err(ContractError::NoPendingOwner {}),
+    };
+
+    if pending.owner != sender {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if let Some(expiry) = pending.expiry {
+        if expiry.is_expired(&env.block) {
+            return Err(ContractError::OwnershipProposalExpired {});
+        }
+    }
+
+    STATE.update(deps.storage, |mut state| -> Result<_, ContractError> {
+        state.owner = pending.owner.clone();
+        Ok(state)
+    })?;
+    PENDING_OWNER.remove(deps.storage);
+
+    Ok(Response {
+        messages: vec![],
+        submessages: vec![],
+        attributes: vec![
+            attr("action", "accept_ownership"),
+            attr("owner", pending.owner),
+        ],
+        data: None,
+    })
+}
+
+pub fn try_cancel_ownership_proposal(
+    deps: DepsMut,
+    sender: Addr,
+) -> Result<Response, ContractError> {
+    if STATE.load(deps.storage)?.owner != sender {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if PENDING_OWNER.may_load(deps.storage)?.is_none() {
+        return Err(ContractError::NoPendingOwner {});
+    }
+    PENDING_OWNER.remove(deps.storage);
+
+    Ok(Response {
+        messages: vec![],
+        submessages: vec![],
+        attributes: vec![attr("action", "cancel_ownership_proposal")],
+        data: None,
+    })
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    // contracts deployed at v0.1.0 did not store a contract version
+    let stored = CONTRACT
+        .may_load(deps.storage)?
+        .unwrap_or_else(|| ContractVersion {
+            contract: CONTRACT_NAME.to_string(),
+            version: v0_1_0::VERSION.to_string(),
+        });
+
+    if stored.contract != CONTRACT_NAME {
+        return Err(ContractError::CannotMigrate {
+            previous_contract: stored.contract,
+        });
+    }
+    if parse_version(&stored.version)? > parse_version(CONTRACT_VERSION)? {
+        return Err(ContractError::CannotMigrateVersion {
+            previous_version: stored.version,
+        });
+    }
+
+    if stored.version == v0_1_0::VERSION {
+        migrate_from_v0_1_0(deps.storage)?;
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response {
+        messages: vec![],
+        submessages: vec![],
+        attributes: vec![
+            attr("action", "migrate"),
+            attr("from_version", stored.version),
+            attr("to_version", CONTRACT_VERSION),
+        ],
+        data: None,
+    })
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::GetInfo {} => to_binary(&query_info(deps, env)?),
+        QueryMsg::PendingOwner {} => to_binary(&query_pending_owner(deps)?),
+        QueryMsg::Allowance { address } => to_binary(&query_allowance(deps, address)?),
+        QueryMsg::VestingInfo { address } => to_binary(&query_vesting_info(deps, env, address)?),
+        QueryMsg::CurrentPrice {} => to_binary(&query_current_price(deps, env)?),
+        QueryMsg::Tiers {} => to_binary(&query_tiers(deps)?),
+        QueryMsg::Clearing {} => to_binary(&query_clearing(deps)?),
+        QueryMsg::Bid { address } => to_binary(&query_bid(deps, address)?),
+        QueryMsg::ReferralStats { address } => to_binary(&query_referral_stats(deps, address)?),
+        QueryMsg::Proceeds {} => to_binary(&query_proceeds(deps)?),
+        QueryMsg::FairLaunch {} => to_binary(&query_fair_launch(deps)?),
+        QueryMsg::Deposit { address } => to_binary(&query_deposit(deps, address)?),
+        QueryMsg::Reconcile {} => to_binary(&query_reconcile(deps, env)?),
+        QueryMsg::ListPrices { start_after, limit } => {
+            to_binary(&query_list_prices(deps, start_after, limit)?)
+        }
+        QueryMsg::ListCw20Prices { start_after, limit } => {
+            to_binary(&query_list_cw20_prices(deps, start_after, limit)?)
+        }
+    }
+}
+
+fn query_info(deps: Deps, env: Env) -> StdResult<InfoResponse> {
+    let state = STATE.load(deps.storage)?;
+    Ok(InfoResponse {
+        phase: state.phase(&env.block),
+        owner: state.owner,
+        cw20_address: state.cw20_address,
+        balance: state.balance,
+        start: state.start,
+        end: state.end,
+        max_per_address: state.max_per_address,
+        min_per_purchase: state.min_per_purchase,
+        hard_cap: state.hard_cap,
+        tokens_sold: state.tokens_sold,
+        merkle_root: state.merkle_root,
+        vesting: state.vesting,
+        pricing: state.pricing,
+        batch_auction: state.batch_auction,
+        fair_launch: state.fair_launch,
+        soft_cap: state.soft_cap,
+        escrow_proceeds: state.escrow_proceeds,
+        split: state.split,
+        fee: state.fee,
+        referral: state.referral,
+        status: state.status,
+        live_balance: state.live_balance,
+        paused: state.paused,
+        guardian: state.guardian,
+    })
+}
+
+fn query_reconcile(deps: Deps, env: Env) -> StdResult<ReconcileResponse> {
+    let state = STATE.load(deps.storage)?;
+    let owed = OWED.may_load(deps.storage)?.unwrap_or_default();
+    let cw20_balance = query_cw20_balance(deps, &env, &state)?;
+    let tracked = state.balance.checked_add(owed)?;
+    Ok(ReconcileResponse {
+        balance: state.balance,
+        owed,
+        cw20_balance,
+        surplus: cw20_balance.saturating_sub(tracked),
+        deficit: tracked.saturating_sub(cw20_balance),
+    })
+}
+
+fn query_pending_owner(deps: Deps) -> StdResult<PendingOwnerResponse> {
+    let pending = PENDING_OWNER.may_load(deps.storage)?;
+    Ok(PendingOwnerResponse {
+        pending_owner: pending.clone().map(|p| p.owner),
+        expiry: pending.and_then(|p| p.expiry),
+    })
+}
+
+fn query_allowance(deps: Deps, address: String) -> StdResult<AllowanceResponse> {
+    let state = STATE.load(deps.storage)?;
+    let address = deps.api.addr_validate(&address)?;
+    let purchased = PURCHASES
+        .may_load(deps.storage, &address)?
+        .unwrap_or_default();
+
+    // the tighter of the address cap, the hard cap and the tokens left on sale
+    let mut remaining = state.balance;
+    if let Some(max) = state.max_per_address {
+        remaining = remaining.min(max.saturating_sub(purchased));
+    }
+    if let Some(hard_cap) = state.hard_cap {
+        remaining = remaining.min(hard_cap.saturating_sub(state.tokens_sold));
+    }
+
+    Ok(AllowanceResponse {
+        purchased,
+        remaining,
+    })
+}
+
+fn query_vesting_info(deps: Deps, env: Env, address: String) -> StdResult<VestingInfoResponse> {
+    let state = STATE.load(deps.storage)?;
+    let address = deps.api.addr_validate(&address)?;
+    let account = VESTING
+        .may_load(deps.storage, &address)?
+        .unwrap_or_default();
+
+    let vested = match state.vesting {
+        Some(schedule) => schedule.vested_amount(account.total, env.block.time)?,
+        None => account.total,
+    };
+
+    Ok(VestingInfoResponse {
+        total: account.total,
+        claimed: account.claimed,
+        claimable: vested.checked_sub(account.claimed)?,
+    })
+}
+
+fn query_current_price(deps: Deps, env: Env) -> StdResult<CurrentPriceResponse> {
+    let state = STATE.load(deps.storage)?;
+    let pricing = match state.pricing {
+        Some(pricing) => pricing,
+        None => return Err(StdError::generic_err("No pricing mode set")),
+    };
+    Ok(CurrentPriceResponse {
+        price: Coin {
+            denom: pricing.denom().to_string(),
+            amount: pricing.current_price(env.block.time, state.tokens_sold)?,
+        },
+    })
+}
+
+fn query_tiers(deps: Deps) -> StdResult<TiersResponse> {
+    let state = STATE.load(deps.storage)?;
+    let tiers = match state
+        .pricing
+        .as_ref()
+        .and_then(|pricing| pricing.tiers_remaining(state.tokens_sold))
+    {
+        Some(tiers) => tiers,
+        None => return Err(StdError::generic_err("Sale is not tiered")),
+    };
+    Ok(TiersResponse {
+        tiers: tiers
+            .into_iter()
+            .map(|(tier, remaining)| TierInfo {
+                supply: tier.supply,
+                price: tier.price,
+                remaining,
+            })
+            .collect(),
+    })
+}
+
+fn query_clearing(deps: Deps) -> StdResult<ClearingResponse> {
+    let clearing = CLEARING.may_load(deps.storage)?.unwrap_or_default();
+    Ok(ClearingResponse {
+        supply: clearing.supply,
+        price: clearing.price,
+        sold: clearing.sold(),
+    })
+}
+
+fn query_bid(deps: Deps, address: String) -> StdResult<BidResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    Ok(BidResponse {
+        bid: BIDS.may_load(deps.storage, &address)?,
+    })
+}
+
+fn query_referral_stats(deps: Deps, address: String) -> StdResult<ReferralStatsResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let stats = REFERRALS
+        .may_load(deps.storage, &address)?
+        .unwrap_or_default();
+    Ok(ReferralStatsResponse {
+        referrals: stats.referrals,
+        volume: stats.volume,
+    })
+}
+
+fn query_proceeds(deps: Deps) -> StdResult<ProceedsResponse> {
+    let cw20_proceeds = CW20_PROCEEDS
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            let (address, amount) = item?;
+            Ok(Cw20Coin {
+                address: String::from_utf8(address)?,
+                amount,
+            })
+        })
+        .filter(|coin| !matches!(coin, Ok(coin) if coin.amount == Uint128(0)))
+        .collect::<StdResult<Vec<Cw20Coin>>>()?;
+    Ok(ProceedsResponse {
+        proceeds: list_proceeds(deps.storage)?,
+        cw20_proceeds,
+    })
+}
+
+fn query_fair_launch(deps: Deps) -> StdResult<FairLaunchResponse> {
+    let totals = FAIR_LAUNCH.may_load(deps.storage)?.unwrap_or_default();
+    Ok(FairLaunchResponse {
+        deposits: totals.deposits,
+        sold: totals.sold,
+        raised: totals.raised,
+    })
+}
+
+fn query_deposit(deps: Deps, address: String) -> StdResult<DepositResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let settlement = SETTLEMENTS
+        .may_load(deps.storage, &address)?
+        .unwrap_or_default();
+    Ok(DepositResponse {
+        deposit: DEPOSITS
+            .may_load(deps.storage, &address)?
+            .unwrap_or_default(),
+        claimed: settlement.claimed,
+        refunded: settlement.refunded,
+    })
+}
+
+fn query_list_prices(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<PricesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let prices = PRICES
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (denom, amount) = item?;
+            Ok(Coin {
+                denom: String::from_utf8(denom)?,
+                amount,
+            })
+        })
+        .collect::<StdResult<Vec<Coin>>>()?;
+
+    Ok(PricesResponse { prices })
+}
+
+fn query_list_cw20_prices(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Cw20PricesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let prices = CW20_PRICES
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (address, amount) = item?;
+            Ok(Cw20Coin {
+                address: String::from_utf8(address)?,
+                amount,
+            })
+        })
+        .collect::<StdResult<Vec<Cw20Coin>>>()?;
+
+    Ok(Cw20PricesResponse { prices })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pricing::PriceTier;
+    use crate::state::{BatchAuction, FairLaunch, Fee, Recipient, Referral, VestingSchedule};
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::{coins, from_binary, from_slice, Storage, Uint128};
+
+    fn sale_msg() -> InstantiateMsg {
+        InstantiateMsg {
+            cw20_address: Addr::unchecked("asdf"),
+            price: Some(Uint128::from(7u128)),
+            denom: Some("token".to_string()),
+            start: None,
+            end: None,
+            max_per_address: None,
+            min_per_purchase: None,
+            hard_cap: None,
+            merkle_root: None,
+            vesting: None,
+            pricing: None,
+            batch_auction: None,
+            fair_launch: None,
+            soft_cap: None,
+            escrow_proceeds: false,
+            split: None,
+            fee: None,
+            referral: None,
+            live_balance: false,
+            guardian: None,
+        }
+    }
+
+    #[test]
+    fn proper_initialization() {
+        let mut deps = mock_dependencies(&[]);
+
+        let msg = sale_msg();
+        let info = mock_info("creator", &coins(1000, "earth"));
+
+        // we can just call .unwrap() to assert this was a success
+        let res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(0, res.messages.len());
+
+        // it worked, let's query the state
+        let msg = QueryMsg::ListPrices {
+            start_after: None,
+            limit: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let value: PricesResponse = from_binary(&res).unwrap();
+        assert_eq!(coins(7, "token"), value.prices);
+    }
+
+    #[test]
+    fn instantiate_msg_defaults() {
+        // messages written before the flags were added still parse
+        let msg: InstantiateMsg =
+            from_slice(br#"{"cw20_address":"asdf","denom":"token","price":"7"}"#).unwrap();
+        assert_eq!(sale_msg(), msg);
+    }
+
+    #[test]
+    fn set_price() {
+        let mut deps = mock_dependencies(&coins(2, "token"));
+
+        let msg = sale_msg();
+        let info = mock_info("creator", &coins(2, "token"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // owner can change price
+        let info = mock_info("creator", &coins(2, "token"));
+        let msg = ExecuteMsg::SetPrice {
+            denom: "token".to_string(),
+            price: Uint128::from(2u128),
+        };
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // check price
+        let msg = QueryMsg::ListPrices {
+            start_after: None,
+            limit: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let value: PricesResponse = from_binary(&res).unwrap();
+        assert_eq!(coins(2, "token"), value.prices);
+
+        // non-owner cannot change price
+        let info = mock_info("imposter", &coins(2, "token"));
+        let msg = ExecuteMsg::SetPrice {
+            price: Uint128::from(10u128),
+            denom: "token".to_string(),
+        };
+        let _res = execute(deps.as_mut(), mock_env(), info, msg);
+        assert!(_res.is_err());
+
+        // check price
+        let msg = QueryMsg::ListPrices {
+            start_after: None,
+            limit: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let value: PricesResponse = from_binary(&res).unwrap();
+        assert_eq!(coins(2, "token"), value.prices);
+    }
+
+    #[test]
+    fn add_and_remove_prices() {
+        let mut deps = mock_dependencies(&[]);
+
+        let msg = InstantiateMsg {
+            denom: Some("ujuno".to_string()),
+            ..sale_msg()
+        };
+        let info = mock_info("creator", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // owner can add more denoms
+        for (denom, price) in &[("uatom", 3u128), ("ibc/usdc", 5u128)] {
+            let info = mock_info("creator", &[]);
+            let msg = ExecuteMsg::SetPrice {
+                denom: denom.to_string(),
+                price: Uint128::from(*price),
+            };
+            let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        }
+
+        // zero prices are rejected
+        let info = mock_info("creator", &[]);
+        let msg = ExecuteMsg::SetPrice {
+            denom: "uosmo".to_string(),
+            price: Uint128(0),
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        match res {
+            Err(ContractError::InvalidZeroPrice {}) => {}
+            _ => panic!("expected InvalidZeroPrice error"),
+        }
+
+        // prices are listed by denom and paginated
+        let msg = QueryMsg::ListPrices {
+            start_after: None,
+            limit: Some(2),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let value: PricesResponse = from_binary(&res).unwrap();
+        assert_eq!(
+            vec![
+                Coin {
+                    denom: "ibc/usdc".to_string(),
+                    amount: Uint128(5)
+                },
+                Coin {
+                    denom: "uatom".to_string(),
+                    amount: Uint128(3)
+                },
+            ],
+            value.prices
+        );
+        let msg = QueryMsg::ListPrices {
+            start_after: Some("uatom".to_string()),
+            limit: Some(2),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let value: PricesResponse = from_binary(&res).unwrap();
+        assert_eq!(coins(7, "ujuno"), value.prices);
+
+        // non-owner cannot remove a denom
+        let info = mock_info("imposter", &[]);
+        let msg = ExecuteMsg::RemovePrice {
+            denom: "uatom".to_string(),
+        };
+        let _res = execute(deps.as_mut(), mock_env(), info, msg);
+        assert!(_res.is_err());
+
+        let info = mock_info("creator", &[]);
+        let msg = ExecuteMsg::RemovePrice {
+            denom: "uatom".to_string(),
+        };
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // removing an unknown denom fails
+        let info = mock_info("creator", &[]);
+        let msg = ExecuteMsg::RemovePrice {
+            denom: "uatom".to_string(),
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        match res {
+            Err(ContractError::PriceNotFound { denom }) => assert_eq!("uatom", denom),
+            _ => panic!("expected PriceNotFound error"),
+        }
+
+        let msg = QueryMsg::ListPrices {
+            start_after: None,
+            limit: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let value: PricesResponse = from_binary(&res).unwrap();
+        assert_eq!(2, value.prices.len());
+    }
+
+    #[test]
+    fn receive_cw20_token() {
+        let mut deps = mock_dependencies(&coins(2, "token"));
+
+        let msg = sale_msg();
+        let info = mock_info("creator", &coins(2, "token"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        // cw20 contract sends tokens to the sale
+        let info = mock_info("asdf", &[]);
+        let msg = ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+            amount: Uint128(10),
+            sender: "asdf".to_string(),
+            msg: to_binary(&ReceiveMsg::Receive {}).unwrap(),
+        });
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // check balance
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetInfo {}).unwrap();
+        let value: InfoResponse = from_binary(&res).unwrap();
+        assert_eq!(Uint128(10), value.balance);
+    }
+
+    #[test]
+    fn receive_cw20_token_only_sale_token() {
+        let mut deps = mock_dependencies(&[]);
+
+        let msg = sale_msg();
+        let info = mock_info("creator", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // anyone else calling the receive hook is rejected
+        let info = mock_info("imposter", &[]);
+        let msg = ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+            amount: Uint128(10),
+            sender: "imposter".to_string(),
+            msg: to_binary(&ReceiveMsg::Receive {}).unwrap(),
+        });
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        match res {
+            Err(ContractError::UnrecognizedToken { address }) => {
+                assert_eq!(address, Addr::unchecked("imposter"))
+            }
+            _ => panic!("expected UnrecognizedToken error"),
+        }
+
+        // balance is untouched
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetInfo {}).unwrap();
+        let value: InfoResponse = from_binary(&res).unwrap();
+        assert_eq!(Uint128(0), value.balance);
+    }
+
+    #[test]
+    fn create_sale_with_receive() {
+        let mut deps = mock_dependencies(&[]);
+
+        let msg = InstantiateMsg {
+            price: None,
+            denom: None,
+            ..sale_msg()
+        };
+        let info = mock_info("creator", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // buying is refused until the sale is funded and priced
+        let info = mock_info("buyer", &coins(10, "ujuno"));
+        let msg = ExecuteMsg::Buy {
+            prices: coins(2, "ujuno"),
+            referrer: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        assert!(matches!(res, Err(ContractError::SaleUninitialized {})));
+
+        // only the owner can configure the sale
+        let create = to_binary(&ReceiveMsg::CreateSale {
+            denom: "ujuno".to_string(),
+            price: Uint128(2),
+        })
+        .unwrap();
+        let info = mock_info("asdf", &[]);
+        let msg = ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+            amount: Uint128(10),
+            sender: "anyone".to_string(),
+            msg: create.clone(),
+        });
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), msg);
+        assert!(matches!(res, Err(ContractError::Unauthorized {})));
+
+        // a malformed CreateSale is rejected rather than taken as a deposit
+        let msg = ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+            amount: Uint128(10),
+            sender: "creator".to_string(),
+            msg: Binary::from(br#"{"create_sale":{"demon":"ujuno","price":"2"}}"#.to_vec()),
+        });
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), msg);
+        assert!(matches!(res, Err(ContractError::Std(_))));
+        let msg = ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+            amount: Uint128(10),
+            sender: "creator".to_string(),
+            msg: to_binary(&ReceiveMsg::Buy { price: Uint128(2) }).unwrap(),
+        });
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), msg);
+        assert!(matches!(res, Err(ContractError::InvalidReceiveMsg {})));
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetInfo {}).unwrap();
+        let value: InfoResponse = from_binary(&res).unwrap();
+        assert_eq!(Uint128(0), value.balance);
+
+        let msg = ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+            amount: Uint128(10),
+            sender: "creator".to_string(),
+            msg: create.clone(),
+        });
+        let _res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetInfo {}).unwrap();
+        let value: InfoResponse = from_binary(&res).unwrap();
+        assert_eq!(Uint128(10), value.balance);
+        assert_eq!(Status::Initialized, value.status);
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::ListPrices {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+        let value: PricesResponse = from_binary(&res).unwrap();
+        assert_eq!(coins(2, "ujuno"), value.prices);
+
+        // the sale can only be configured once
+        let msg = ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+            amount: Uint128(10),
+            sender: "creator".to_string(),
+            msg: create,
+        });
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        assert!(matches!(res, Err(ContractError::SaleInitialized {})));
+
+        let info = mock_info("buyer", &coins(10, "ujuno"));
+        let msg = ExecuteMsg::Buy {
+            prices: coins(2, "ujuno"),
+            referrer: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(2, res.messages.len());
+    }
+
+    #[test]
+    fn uninitialized_until_funded() {
+        let mut deps = mock_dependencies(&[]);
+
+        // a denom without a price is rejected
+        let mut msg = InstantiateMsg {
+            price: None,
+            denom: Some("ujuno".to_string()),
+            ..sale_msg()
+        };
+        let info = mock_info("creator", &[]);
+        let res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg.clone());
+        assert!(matches!(res, Err(ContractError::IncompletePrice {})));
+
+        msg.denom = None;
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // a plain deposit funds the sale but it still has no price
+        let info = mock_info("asdf", &[]);
+        let msg = ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+            amount: Uint128(10),
+            sender: "creator".to_string(),
+            msg: to_binary(&ReceiveMsg::Receive {}).unwrap(),
+        });
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetInfo {}).unwrap();
+        let value: InfoResponse = from_binary(&res).unwrap();
+        assert_eq!(Status::Uninitialized, value.status);
+
+        // setting a price completes the setup
+        let info = mock_info("creator", &[]);
+        let msg = ExecuteMsg::SetPrice {
+            denom: "ujuno".to_string(),
+            price: Uint128(2),
+        };
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetInfo {}).unwrap();
+        let value: InfoResponse = from_binary(&res).unwrap();
+        assert_eq!(Status::Initialized, value.status);
+    }
+
+    #[test]
+    fn buy_token() {
+        let mut deps = mock_dependencies(&coins(2, "token"));
+        let price: Uint128 = Uint128::from(7u128);
+        let denom: String = "utoken".to_string();
+        let msg = InstantiateMsg {
+            price: Some(price),
+            denom: Some(denom.clone()),
+            ..sale_msg()
+        };
+        let info = mock_info("creator", &coins(2, "utoken"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("asdf", &[]);
+        let msg = ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+            amount: Uint128(4),
+            sender: "asdf".to_string(),
+            msg: to_binary(&ReceiveMsg::Receive {}).unwrap(),
+        });
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // basic buy
+        let msg = ExecuteMsg::Buy {
+            prices: coins(7, &denom),
+            referrer: None,
+        };
+        let info = mock_info("buyer", &coins(14, "utoken"));
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(_res.attributes.first().unwrap(), &attr("amount", 2));
+
+        // over pay
+        let msg = ExecuteMsg::Buy {
+            prices: coins(price.u128(), &denom),
+            referrer: None,
+        };
+        let info = mock_info("buyer", &coins(20, "utoken"));
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(_res.attributes.first().unwrap(), &attr("amount", 2));
+        assert_eq!(_res.attributes[1], attr("paid", "14utoken"));
+        assert_eq!(_res.attributes[2], attr("refunded", "6utoken"));
+
+        // owner only receives amount * price, the dust goes back to the buyer
+        assert_eq!(
+            _res.messages[1],
+            CosmosMsg::Bank(cosmwasm_std::BankMsg::Send {
+                to_address: "creator".to_string(),
+                amount: coins(14, "utoken"),
+            })
+        );
+        assert_eq!(
+            _res.messages[2],
+            CosmosMsg::Bank(cosmwasm_std::BankMsg::Send {
+                to_address: "buyer".to_string(),
+                amount: coins(6, "utoken"),
+            })
+        );
+
+        // wrong denom
+        let msg = ExecuteMsg::Buy {
+            prices: coins(price.u128(), &denom),
+            referrer: None,
+        };
+        let info = mock_info("buyer", &coins(2, "uwrong"));
+        let _res = execute(deps.as_mut(), mock_env(), info, msg);
+        assert!(_res.is_err());
+    }
+
+    #[test]
+    fn buy_token_with_multiple_coins() {
+        let mut deps = mock_dependencies(&coins(2, "token"));
+
+        let price: Uint128 = Uint128::from(7u128);
+        let denom: String = "utoken".to_string();
+        let msg = InstantiateMsg {
+            price: Some(price),
+            denom: Some(denom.clone()),
+            ..sale_msg()
+        };
+        let info = mock_info("creator", &coins(2, "utoken"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("asdf", &[]);
+        let msg = ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+            amount: Uint128(4),
+            sender: "asdf".to_string(),
+            msg: to_binary(&ReceiveMsg::Receive {}).unwrap(),
+        });
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // buy with three types of funds
+        let msg = ExecuteMsg::Buy {
+            prices: coins(price.u128(), &denom),
+            referrer: None,
+        };
+        let funds: [Coin; 3] = [
+            Coin {
+                amount: Uint128(7),
+                denom: "utoken".to_string(),
+            },
+            Coin {
+                amount: Uint128(7),
+                denom: "utoken".to_string(),
+            },
+            Coin {
+                amount: Uint128(7),
+                denom: "ufake".to_string(),
+            },
+        ];
+        let info = mock_info("buyer", &funds);
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(_res.attributes.first().unwrap(), &attr("amount", 2));
+        assert_eq!(_res.attributes[1], attr("paid", "14utoken"));
+        assert_eq!(_res.attributes[2], attr("refunded", "7ufake"));
+
+        // the unrelated denom is refunded to the buyer
+        assert_eq!(
+            _res.messages[2],
+            CosmosMsg::Bank(cosmwasm_std::BankMsg::Send {
+                to_address: "buyer".to_string(),
+                amount: coins(7, "ufake"),
+            })
+        );
+    }
+
+    #[test]
+    fn buy_token_with_multiple_denoms() {
+        let mut deps = mock_dependencies(&[]);
+
+        let msg = InstantiateMsg {
+            denom: Some("ujuno".to_string()),
+            ..sale_msg()
+        };
+        let info = mock_info("creator", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("creator", &[]);
+        let msg = ExecuteMsg::SetPrice {
+            denom: "uatom".to_string(),
+            price: Uint128(3),
+        };
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("asdf", &[]);
+        let msg = ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+            amount: Uint128(10),
+            sender: "creator".to_string(),
+            msg: to_binary(&ReceiveMsg::Receive {}).unwrap(),
+        });
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let funds = vec![
+            Coin {
+                amount: Uint128(15),
+                denom: "ujuno".to_string(),
+            },
+            Coin {
+                amount: Uint128(10),
+                denom: "uatom".to_string(),
+            },
+        ];
+        let prices = vec![
+            Coin {
+                amount: Uint128(7),
+                denom: "ujuno".to_string(),
+            },
+            Coin {
+                amount: Uint128(3),
+                denom: "uatom".to_string(),
+            },
+        ];
+
+        // every denom paid with must be quoted
+        let info = mock_info("buyer", &funds);
+        let msg = ExecuteMsg::Buy {
+            prices: coins(7, "ujuno"),
+            referrer: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        match res {
+            Err(ContractError::PriceNotProvided { denom }) => assert_eq!("uatom", denom),
+            _ => panic!("expected PriceNotProvided error"),
+        }
+
+        // stale quotes are rejected
+        let info = mock_info("buyer", &funds);
+        let msg = ExecuteMsg::Buy {
+            prices: vec![prices[0].clone(), coins(2, "uatom")[0].clone()],
+            referrer: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        match res {
+            Err(ContractError::PriceNotCurrentError {
+                price_current,
+                price_provided,
+                ..
+            }) => {
+                assert_eq!(Uint128(3), price_current);
+                assert_eq!(Uint128(2), price_provided);
+            }
+            _ => panic!("expected PriceNotCurrentError error"),
+        }
+
+        // 15ujuno buys 2 and 10uatom buys 3
+        let info = mock_info("buyer", &funds);
+        let msg = ExecuteMsg::Buy {
+            prices,
+            referrer: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(res.attributes[0], attr("amount", 5));
+        assert_eq!(res.attributes[1], attr("paid", "14ujuno,9uatom"));
+        assert_eq!(res.attributes[2], attr("refunded", "1ujuno,1uatom"));
+        assert_eq!(
+            res.messages[1],
+            CosmosMsg::Bank(cosmwasm_std::BankMsg::Send {
+                to_address: "creator".to_string(),
+                amount: vec![
+                    Coin {
+                        amount: Uint128(14),
+                        denom: "ujuno".to_string(),
+                    },
+                    Coin {
+                        amount: Uint128(9),
+                        denom: "uatom".to_string(),
+                    },
+                ],
+            })
+        );
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetInfo {}).unwrap();
+        let value: InfoResponse = from_binary(&res).unwrap();
+        assert_eq!(Uint128(5), value.balance);
+    }
+
+    #[test]
+    fn buy_token_with_cw20() {
+        let mut deps = mock_dependencies(&[]);
+
+        let msg = InstantiateMsg {
+            denom: Some("ujuno".to_string()),
+            ..sale_msg()
+        };
+        let info = mock_info("creator", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("asdf", &[]);
+        let msg = ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+            amount: Uint128(10),
+            sender: "creator".to_string(),
+            msg: to_binary(&ReceiveMsg::Receive {}).unwrap(),
+        });
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // non-owner cannot accept a payment cw20
+        let info = mock_info("imposter", &[]);
+        let msg = ExecuteMsg::SetCw20Price {
+            address: "stable".to_string(),
+            price: Uint128(3),
+        };
+        let _res = execute(deps.as_mut(), mock_env(), info, msg);
+        assert!(_res.is_err());
+
+        // the cw20 being sold cannot be used for payment
+        let info = mock_info("creator", &[]);
+        let msg = ExecuteMsg::SetCw20Price {
+            address: "asdf".to_string(),
+            price: Uint128(3),
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        match res {
+            Err(ContractError::InvalidPaymentToken { address }) => {
+                assert_eq!(Addr::unchecked("asdf"), address)
+            }
+            _ => panic!("expected InvalidPaymentToken error"),
+        }
+
+        let info = mock_info("creator", &[]);
+        let msg = ExecuteMsg::SetCw20Price {
+            address: "stable".to_string(),
+            price: Uint128(3),
+        };
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = QueryMsg::ListCw20Prices {
+            start_after: None,
+            limit: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let value: Cw20PricesResponse = from_binary(&res).unwrap();
+        assert_eq!(
+            vec![Cw20Coin {
+                address: "stable".to_string(),
+                amount: Uint128(3)
+            }],
+            value.prices
+        );
+
+        // stale price is rejected
+        let info = mock_info("stable", &[]);
+        let msg = ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+            amount: Uint128(10),
+            sender: "buyer".to_string(),
+            msg: to_binary(&ReceiveMsg::Buy { price: Uint128(2) }).unwrap(),
+        });
+        let _res = execute(deps.as_mut(), mock_env(), info, msg);
+        assert!(_res.is_err());
+
+        // 10 stable buys 3 tokens, 9 goes to the owner and 1 back to the buyer
+        let info = mock_info("stable", &[]);
+        let msg = ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+            amount: Uint128(10),
+            sender: "buyer".to_string(),
+            msg: to_binary(&ReceiveMsg::Buy { price: Uint128(3) }).unwrap(),
+        });
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(res.attributes[0], attr("amount", 3));
+        assert_eq!(res.attributes[1], attr("paid", "9stable"));
+        assert_eq!(res.attributes[2], attr("refunded", "1stable"));
+        assert_eq!(
+            res.messages,
+            vec![
+                cw20_transfer_msg(
+                    &Addr::unchecked("asdf"),
+                    &Addr::unchecked("buyer"),
+                    Uint128(3)
+                )
+                .unwrap(),
+                cw20_transfer_msg(
+                    &Addr::unchecked("stable"),
+                    &Addr::unchecked("creator"),
+                    Uint128(9)
+                )
+                .unwrap(),
+                cw20_transfer_msg(
+                    &Addr::unchecked("stable"),
+                    &Addr::unchecked("buyer"),
+                    Uint128(1)
+                )
+                .unwrap(),
+            ]
+        );
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetInfo {}).unwrap();
+        let value: InfoResponse = from_binary(&res).unwrap();
+        assert_eq!(Uint128(7), value.balance);
+
+        // a payment cw20 can only buy
+        for msg in [
+            ReceiveMsg::Receive {},
+            ReceiveMsg::CreateSale {
+                denom: "token".to_string(),
+                price: Uint128(7),
+            },
+        ] {
+            let info = mock_info("stable", &[]);
+            let msg = ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+                amount: Uint128(10),
+                sender: "buyer".to_string(),
+                msg: to_binary(&msg).unwrap(),
+            });
+            let res = execute(deps.as_mut(), mock_env(), info, msg);
+            match res {
+                Err(ContractError::InvalidReceiveMsg {}) => {}
+                _ => panic!("expected InvalidReceiveMsg error"),
+            }
+        }
+
+        // once removed the payment cw20 is no longer recognized
+        let info = mock_info("creator", &[]);
+        let msg = ExecuteMsg::RemoveCw20Price {
+            address: "stable".to_string(),
+        };
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("stable", &[]);
+        let msg = ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+            amount: Uint128(10),
+            sender: "buyer".to_string(),
+            msg: to_binary(&ReceiveMsg::Buy { price: Uint128(3) }).unwrap(),
+        });
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        match res {
+            Err(ContractError::UnrecognizedToken { address }) => {
+                assert_eq!(Addr::unchecked("stable"), address)
+            }
+            _ => panic!("expected UnrecognizedToken error"),
+        }
+    }
+
+    #[test]
+    fn sale_window() {
+        let mut deps = mock_dependencies(&[]);
+        let env = mock_env();
+        let height = env.block.height;
+
+        // start must come before end
+        let msg = InstantiateMsg {
+            denom: Some("ujuno".to_string()),
+            start: Some(Expiration::AtHeight(height + 20)),
+            end: Some(Expiration::AtHeight(height + 10)),
+            ..sale_msg()
+        };
+        let info = mock_info("creator", &[]);
+        let res = instantiate(deps.as_mut(), env.clone(), info, msg);
+        match res {
+            Err(ContractError::InvalidSaleWindow {}) => {}
+            _ => panic!("expected InvalidSaleWindow error"),
+        }
+
+        let msg = InstantiateMsg {
+            denom: Some("ujuno".to_string()),
+            start: Some(Expiration::AtHeight(height + 10)),
+            end: Some(Expiration::AtHeight(height + 20)),
+            ..sale_msg()
+        };
+        let info = mock_info("creator", &[]);
+        let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        let info = mock_info("asdf", &[]);
+        let msg = ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+            amount: Uint128(10),
+            sender: "creator".to_string(),
+            msg: to_binary(&ReceiveMsg::Receive {}).unwrap(),
+        });
+        let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::GetInfo {}).unwrap();
+        let value: InfoResponse = from_binary(&res).unwrap();
+        assert_eq!(Phase::Pending, value.phase);
+
+        // cannot buy before the start
+        let info = mock_info("buyer", &coins(7, "ujuno"));
+        let msg = ExecuteMsg::Buy {
+            prices: coins(7, "ujuno"),
+            referrer: None,
+        };
+        let res = execute(deps.as_mut(), env.clone(), info, msg);
+        match res {
+            Err(ContractError::SaleNotStarted {}) => {}
+            _ => panic!("expected SaleNotStarted error"),
+        }
+
+        // non-owner cannot move the window
+        let info = mock_info("imposter", &[]);
+        let msg = ExecuteMsg::SetSaleWindow {
+            start: None,
+            end: None,
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info, msg);
+        assert!(_res.is_err());
+
+        // owner can shorten the window before it starts
+        let info = mock_info("creator", &[]);
+        let msg = ExecuteMsg::SetSaleWindow {
+            start: Some(Expiration::AtHeight(height + 5)),
+            end: Some(Expiration::AtHeight(height + 15)),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        let mut open_env = env.clone();
+        open_env.block.height = height + 5;
+        let res = query(deps.as_ref(), open_env.clone(), QueryMsg::GetInfo {}).unwrap();
+        let value: InfoResponse = from_binary(&res).unwrap();
+        assert_eq!(Phase::Open, value.phase);
+        assert_eq!(Some(Expiration::AtHeight(height + 15)), value.end);
+
+        let info = mock_info("buyer", &coins(7, "ujuno"));
+        let msg = ExecuteMsg::Buy {
+            prices: coins(7, "ujuno"),
+            referrer: None,
+        };
+        let _res = execute(deps.as_mut(), open_env.clone(), info, msg).unwrap();
+
+        // the window is fixed once the sale started
+        let info = mock_info("creator", &[]);
+        let msg = ExecuteMsg::SetSaleWindow {
+            start: None,
+            end: Some(Expiration::AtHeight(height + 100)),
+        };
+        let res = execute(deps.as_mut(), open_env, info, msg);
+        match res {
+            Err(ContractError::SaleStarted {}) => {}
+            _ => panic!("expected SaleStarted error"),
+        }
+
+        let mut ended_env = env;
+        ended_env.block.height = height + 15;
+        let res = query(deps.as_ref(), ended_env.clone(), QueryMsg::GetInfo {}).unwrap();
+        let value: InfoResponse = from_binary(&res).unwrap();
+        assert_eq!(Phase::Ended, value.phase);
+
+        // cannot buy after the end, with native or cw20 funds
+        let info = mock_info("buyer", &coins(7, "ujuno"));
+        let msg = ExecuteMsg::Buy {
+            prices: coins(7, "ujuno"),
+            referrer: None,
+        };
+        let res = execute(deps.as_mut(), ended_env.clone(), info, msg);
+        match res {
+            Err(ContractError::SaleEnded {}) => {}
+            _ => panic!("expected SaleEnded error"),
+        }
+
+        let info = mock_info("creator", &[]);
+        let msg = ExecuteMsg::SetCw20Price {
+            address: "stable".to_string(),
+            price: Uint128(3),
+        };
+        let _res = execute(deps.as_mut(), ended_env.clone(), info, msg).unwrap();
+        let info = mock_info("stable", &[]);
+        let msg = ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+            amount: Uint128(3),
+            sender: "buyer".to_string(),
+            msg: to_binary(&ReceiveMsg::Buy { price: Uint128(3) }).unwrap(),
+        });
+        let res = execute(deps.as_mut(), ended_env, info, msg);
+        match res {
+            Err(ContractError::SaleEnded {}) => {}
+            _ => panic!("expected SaleEnded error"),
+        }
+    }
+
+    #[test]
+    fn purchase_limits() {
+        let mut deps = mock_dependencies(&[]);
+
+        let msg = InstantiateMsg {
+            price: Some(Uint128::from(1u128)),
+            denom: Some("ujuno".to_string()),
+            max_per_address: Some(Uint128(5)),
+            min_per_purchase: Some(Uint128(2)),
+            hard_cap: Some(Uint128(8)),
+            ..sale_msg()
+        };
+        let info = mock_info("creator", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("asdf", &[]);
+        let msg = ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+            amount: Uint128(20),
+            sender: "creator".to_string(),
+            msg: to_binary(&ReceiveMsg::Receive {}).unwrap(),
+        });
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let buy = |deps: &mut cosmwasm_std::OwnedDeps<_, _, _>, buyer: &str, amount: u128| {
+            let info = mock_info(buyer, &coins(amount, "ujuno"));
+            let msg = ExecuteMsg::Buy {
+                prices: coins(1, "ujuno"),
+                referrer: None,
+            };
+            execute(deps.as_mut(), mock_env(), info, msg)
+        };
+
+        // too small
+        match buy(&mut deps, "alice", 1) {
+            Err(ContractError::PurchaseBelowMinimum { min }) => assert_eq!(Uint128(2), min),
+            _ => panic!("expected PurchaseBelowMinimum error"),
+        }
+
+        // alice fills her allocation
+        buy(&mut deps, "alice", 5).unwrap();
+        match buy(&mut deps, "alice", 2) {
+            Err(ContractError::AddressCapExceeded { remaining }) => {
+                assert_eq!(Uint128(0), remaining)
+            }
+            _ => panic!("expected AddressCapExceeded error"),
+        }
+
+        let msg = QueryMsg::Allowance {
+            address: "alice".to_string(),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let value: AllowanceResponse = from_binary(&res).unwrap();
+        assert_eq!(Uint128(5), value.purchased);
+        assert_eq!(Uint128(0), value.remaining);
+
+        // bob is limited by the hard cap
+        let msg = QueryMsg::Allowance {
+            address: "bob".to_string(),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let value: AllowanceResponse = from_binary(&res).unwrap();
+        assert_eq!(Uint128(0), value.purchased);
+        assert_eq!(Uint128(3), value.remaining);
+
+        match buy(&mut deps, "bob", 4) {
+            Err(ContractError::HardCapExceeded { remaining }) => assert_eq!(Uint128(3), remaining),
+            _ => panic!("expected HardCapExceeded error"),
+        }
+        buy(&mut deps, "bob", 3).unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetInfo {}).unwrap();
+        let value: InfoResponse = from_binary(&res).unwrap();
+        assert_eq!(Uint128(8), value.tokens_sold);
+        assert_eq!(Uint128(12), value.balance);
+
+        let msg = QueryMsg::Allowance {
+            address: "carol".to_string(),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let value: AllowanceResponse = from_binary(&res).unwrap();
+        assert_eq!(Uint128(0), value.remaining);
+    }
+
+    #[test]
+    fn allowlist_phase() {
+        let mut deps = mock_dependencies(&[]);
+
+        // allowlist of 300 buyers, buyer{i} may buy i + 1 tokens
+        let leaves: Vec<[u8; 32]> = (0..300u128)
+            .map(|i| leaf_hash(&Addr::unchecked(format!("buyer{}", i)), Uint128(i + 1)))
+            .collect();
+        let (root, proofs) = crate::merkle::build_tree(&leaves);
+
+        let msg = InstantiateMsg {
+            price: Some(Uint128::from(1u128)),
+            denom: Some("ujuno".to_string()),
+            merkle_root: Some(root.clone()),
+            ..sale_msg()
+        };
+        let info = mock_info("creator", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("asdf", &[]);
+        let msg = ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+            amount: Uint128(1000),
+            sender: "creator".to_string(),
+            msg: to_binary(&ReceiveMsg::Receive {}).unwrap(),
+        });
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // public buys are closed while the allowlist is set
+        let info = mock_info("buyer41", &coins(10, "ujuno"));
+        let msg = ExecuteMsg::Buy {
+            prices: coins(1, "ujuno"),
+            referrer: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        match res {
+            Err(ContractError::WhitelistOnly {}) => {}
+            _ => panic!("expected WhitelistOnly error"),
+        }
+
+        // claiming a larger allocation fails the proof
+        let info = mock_info("buyer41", &coins(10, "ujuno"));
+        let msg = ExecuteMsg::WhitelistBuy {
+            prices: coins(1, "ujuno"),
+            allocation: Uint128(100),
+            proof: proofs[41].clone(),
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        match res {
+            Err(ContractError::InvalidProof {}) => {}
+            _ => panic!("expected InvalidProof error"),
+        }
+
+        // someone else's proof does not work either
+        let info = mock_info("imposter", &coins(10, "ujuno"));
+        let msg = ExecuteMsg::WhitelistBuy {
+            prices: coins(1, "ujuno"),
+            allocation: Uint128(42),
+            proof: proofs[41].clone(),
+        };
+        let _res = execute(deps.as_mut(), mock_env(), info, msg);
+        assert!(_res.is_err());
+
+        let info = mock_info("buyer41", &coins(30, "ujuno"));
+        let msg = ExecuteMsg::WhitelistBuy {
+            prices: coins(1, "ujuno"),
+            allocation: Uint128(42),
+            proof: proofs[41].clone(),
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(res.attributes[0], attr("amount", 30));
+
+        // the allocation caps the total bought
+        let info = mock_info("buyer41", &coins(13, "ujuno"));
+        let msg = ExecuteMsg::WhitelistBuy {
+            prices: coins(1, "ujuno"),
+            allocation: Uint128(42),
+            proof: proofs[41].clone(),
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        match res {
+            Err(ContractError::AllocationExceeded { remaining }) => {
+                assert_eq!(Uint128(12), remaining)
+            }
+            _ => panic!("expected AllocationExceeded error"),
+        }
+
+        // allowlisted buyers can pay with a cw20 as well
+        let info = mock_info("creator", &[]);
+        let msg = ExecuteMsg::SetCw20Price {
+            address: "stable".to_string(),
+            price: Uint128(2),
+        };
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let info = mock_info("stable", &[]);
+        let msg = ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+            amount: Uint128(400),
+            sender: "buyer299".to_string(),
+            msg: to_binary(&ReceiveMsg::WhitelistBuy {
+                price: Uint128(2),
+                allocation: Uint128(300),
+                proof: proofs[299].clone(),
+            })
+            .unwrap(),
+        });
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(res.attributes[0], attr("amount", 200));
+
+        // only the owner can rotate the root, and it must be a valid hash
+        let info = mock_info("imposter", &[]);
+        let msg = ExecuteMsg::SetMerkleRoot { merkle_root: None };
+        let _res = execute(deps.as_mut(), mock_env(), info, msg);
+        assert!(_res.is_err());
+
+        let info = mock_info("creator", &[]);
+        let msg = ExecuteMsg::SetMerkleRoot {
+            merkle_root: Some("not a hash".to_string()),
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        match res {
+            Err(ContractError::InvalidMerkleRoot {}) => {}
+            _ => panic!("expected InvalidMerkleRoot error"),
+        }
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetInfo {}).unwrap();
+        let value: InfoResponse = from_binary(&res).unwrap();
+        assert_eq!(Some(root), value.merkle_root);
+
+        // clearing the root opens the sale to everyone
+        let info = mock_info("creator", &[]);
+        let msg = ExecuteMsg::SetMerkleRoot { merkle_root: None };
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("imposter", &coins(10, "ujuno"));
+        let msg = ExecuteMsg::Buy {
+            prices: coins(1, "ujuno"),
+            referrer: None,
+        };
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    }
+
+    #[test]
+    fn vesting_claims() {
+        let mut deps = mock_dependencies(&[]);
+        let env = mock_env();
+        let now = env.block.time;
+
+        let schedule = VestingSchedule {
+            start: now,
+            cliff: now.plus_seconds(100),
+            end: now.plus_seconds(1000),
+        };
+
+        // the cliff cannot come after the end
+        let msg = InstantiateMsg {
+            price: Some(Uint128::from(1u128)),
+            denom: Some("ujuno".to_string()),
+            vesting: Some(VestingSchedule {
+                cliff: now.plus_seconds(2000),
+                ..schedule
+            }),
+            ..sale_msg()
+        };
+        let info = mock_info("creator", &[]);
+        let res = instantiate(deps.as_mut(), env.clone(), info, msg);
+        match res {
+            Err(ContractError::InvalidVestingSchedule {}) => {}
+            _ => panic!("expected InvalidVestingSchedule error"),
+        }
+
+        let msg = InstantiateMsg {
+            price: Some(Uint128::from(1u128)),
+            denom: Some("ujuno".to_string()),
+            vesting: Some(schedule),
+            ..sale_msg()
+        };
+        let info = mock_info("creator", &[]);
+        let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        let info = mock_info("asdf", &[]);
+        let msg = ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+            amount: Uint128(1000),
+            sender: "creator".to_string(),
+            msg: to_binary(&ReceiveMsg::Receive {}).unwrap(),
+        });
+        let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        // bought tokens stay in the contract, only the owner is paid
+        let info = mock_info("buyer", &coins(100, "ujuno"));
+        let msg = ExecuteMsg::Buy {
+            prices: coins(1, "ujuno"),
+            referrer: None,
+        };
+        let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![CosmosMsg::Bank(cosmwasm_std::BankMsg::Send {
+                to_address: "creator".to_string(),
+                amount: coins(100, "ujuno"),
+            })]
+        );
+
+        let msg = QueryMsg::VestingInfo {
+            address: "buyer".to_string(),
+        };
+        let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let value: VestingInfoResponse = from_binary(&res).unwrap();
+        assert_eq!(Uint128(100), value.total);
+        assert_eq!(Uint128(0), value.claimable);
+
+        // nothing before the cliff
+        let mut cliff_env = env.clone();
+        cliff_env.block.time = now.plus_seconds(99);
+        let info = mock_info("buyer", &[]);
+        let res = execute(deps.as_mut(), cliff_env, info, ExecuteMsg::Claim {});
+        match res {
+            Err(ContractError::NothingToClaim {}) => {}
+            _ => panic!("expected NothingToClaim error"),
+        }
+
+        // half way through, half is released
+        let mut half_env = env.clone();
+        half_env.block.time = now.plus_seconds(500);
+        let info = mock_info("buyer", &[]);
+        let res = execute(deps.as_mut(), half_env.clone(), info, ExecuteMsg::Claim {}).unwrap();
+        assert_eq!(res.attributes[1], attr("amount", 50));
+        assert_eq!(
+            res.messages,
+            vec![cw20_transfer_msg(
+                &Addr::unchecked("asdf"),
+                &Addr::unchecked("buyer"),
+                Uint128(50)
+            )
+            .unwrap()]
+        );
+
+        let msg = QueryMsg::VestingInfo {
+            address: "buyer".to_string(),
+        };
+        let res = query(deps.as_ref(), half_env.clone(), msg).unwrap();
+        let value: VestingInfoResponse = from_binary(&res).unwrap();
+        assert_eq!(Uint128(50), value.claimed);
+        assert_eq!(Uint128(0), value.claimable);
+
+        let info = mock_info("buyer", &[]);
+        let _res = execute(deps.as_mut(), half_env, info, ExecuteMsg::Claim {});
+        assert!(_res.is_err());
+
+        // everything is released at the end
+        let mut end_env = env;
+        end_env.block.time = now.plus_seconds(5000);
+        let info = mock_info("buyer", &[]);
+        let res = execute(deps.as_mut(), end_env.clone(), info, ExecuteMsg::Claim {}).unwrap();
+        assert_eq!(res.attributes[1], attr("amount", 50));
+
+        let msg = QueryMsg::VestingInfo {
+            address: "buyer".to_string(),
+        };
+        let res = query(deps.as_ref(), end_env, msg).unwrap();
+        let value: VestingInfoResponse = from_binary(&res).unwrap();
+        assert_eq!(Uint128(100), value.total);
+        assert_eq!(Uint128(100), value.claimed);
+        assert_eq!(Uint128(0), value.claimable);
+    }
+
+    #[test]
+    fn dutch_auction_pricing() {
+        let mut deps = mock_dependencies(&[]);
+        let now = mock_env().block.time;
+
+        let dutch_auction = |start_price: u128| Pricing::DutchAuction {
+            denom: "ujuno".to_string(),
+            start_price: Uint128(start_price),
+            floor_price: Uint128(10),
+            start_time: now,
+            end_time: now.plus_seconds(100),
+        };
+        let mut msg = InstantiateMsg {
+            pricing: Some(dutch_auction(5)),
+            ..sale_msg()
+        };
+
+        // the start price cannot be below the floor
+        let info = mock_info("creator", &[]);
+        let res = instantiate(deps.as_mut(), mock_env(), info, msg.clone());
+        match res {
+            Err(ContractError::InvalidPricing {}) => {}
+            _ => panic!("expected InvalidPricing error"),
+        }
+
+        msg.pricing = Some(dutch_auction(50));
+        let info = mock_info("creator", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("asdf", &[]);
+        let msg = ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+            amount: Uint128(10),
+            sender: "creator".to_string(),
+            msg: to_binary(&ReceiveMsg::Receive {}).unwrap(),
+        });
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let mut env = mock_env();
+        env.block.time = now.plus_seconds(50);
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::CurrentPrice {}).unwrap();
+        let value: CurrentPriceResponse = from_binary(&res).unwrap();
+        assert_eq!(Coin::new(30, "ujuno"), value.price);
+
+        // the fixed price list is ignored, only the auction denom buys
+        let info = mock_info("buyer", &coins(100, "token"));
+        let msg = ExecuteMsg::Buy {
+            prices: coins(7, "token"),
+            referrer: None,
+        };
+        let res = execute(deps.as_mut(), env.clone(), info, msg);
+        match res {
+            Err(ContractError::IncorretFunds {}) => {}
+            _ => panic!("expected IncorretFunds error"),
+        }
+
+        let info = mock_info("buyer", &coins(100, "ujuno"));
+        let msg = ExecuteMsg::Buy {
+            prices: coins(30, "ujuno"),
+            referrer: None,
+        };
+        let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        assert_eq!(res.attributes[0], attr("amount", 3));
+        assert_eq!(res.attributes[1], attr("paid", "90ujuno"));
+        assert_eq!(res.attributes[2], attr("refunded", "10ujuno"));
+
+        // cw20 payments are not priced by the auction
+        let info = mock_info("stable", &[]);
+        let msg = ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+            amount: Uint128(10),
+            sender: "buyer".to_string(),
+            msg: to_binary(&ReceiveMsg::Buy { price: Uint128(3) }).unwrap(),
+        });
+        let res = execute(deps.as_mut(), env, info, msg);
+        match res {
+            Err(ContractError::Cw20PaymentDisabled {}) => {}
+            _ => panic!("expected Cw20PaymentDisabled error"),
+        }
+    }
+
+    #[test]
+    fn tiered_pricing() {
+        let mut deps = mock_dependencies(&[]);
+
+        let tier = |supply: u128, price: u128| PriceTier {
+            supply: Uint128(supply),
+            price: Uint128(price),
+        };
+        let msg = InstantiateMsg {
+            pricing: Some(Pricing::Tiered {
+                denom: "ujuno".to_string(),
+                tiers: vec![tier(5, 10), tier(5, 20), tier(10, 50)],
+            }),
+            ..sale_msg()
+        };
+        let info = mock_info("creator", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("asdf", &[]);
+        let msg = ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+            amount: Uint128(100),
+            sender: "creator".to_string(),
+            msg: to_binary(&ReceiveMsg::Receive {}).unwrap(),
+        });
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // 5 at 10 and 2 at 20 is an average of 13, above the price provided
+        let info = mock_info("buyer", &coins(95, "ujuno"));
+        let msg = ExecuteMsg::Buy {
+            prices: coins(12, "ujuno"),
+            referrer: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        match res {
+            Err(ContractError::PriceAboveLimit { .. }) => {}
+            _ => panic!("expected PriceAboveLimit error"),
+        }
+
+        let info = mock_info("buyer", &coins(95, "ujuno"));
+        let msg = ExecuteMsg::Buy {
+            prices: coins(20, "ujuno"),
+            referrer: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(res.attributes[0], attr("amount", 7));
+        assert_eq!(res.attributes[1], attr("paid", "90ujuno"));
+        assert_eq!(res.attributes[2], attr("refunded", "5ujuno"));
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Tiers {}).unwrap();
+        let value: TiersResponse = from_binary(&res).unwrap();
+        let remaining: Vec<Uint128> = value.tiers.iter().map(|t| t.remaining).collect();
+        assert_eq!(vec![Uint128(0), Uint128(3), Uint128(10)], remaining);
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::CurrentPrice {}).unwrap();
+        let value: CurrentPriceResponse = from_binary(&res).unwrap();
+        assert_eq!(Coin::new(20, "ujuno"), value.price);
+    }
+
+    #[test]
+    fn batch_auction_undersubscribed() {
+        let mut deps = mock_dependencies(&[]);
+        let end = mock_env().block.time.plus_seconds(100);
+
+        let mut msg = InstantiateMsg {
+            batch_auction: Some(BatchAuction {
+                denom: "ujuno".to_string(),
+                reserve_price: Uint128(2),
+            }),
+            ..sale_msg()
+        };
+
+        // an auction without an end can never be finalized
+        let info = mock_info("creator", &[]);
+        let res = instantiate(deps.as_mut(), mock_env(), info, msg.clone());
+        match res {
+            Err(ContractError::InvalidBatchAuction {}) => {}
+            _ => panic!("expected InvalidBatchAuction error"),
+        }
+
+        msg.end = Some(Expiration::AtTime(end));
+
+        // bids are not checked against an allowlist
+        let root = "ab".repeat(32);
+        let info = mock_info("creator", &[]);
+        let allowlisted = InstantiateMsg {
+            merkle_root: Some(root.clone()),
+            ..msg.clone()
+        };
+        let res = instantiate(deps.as_mut(), mock_env(), info, allowlisted);
+        match res {
+            Err(ContractError::InvalidBatchAuction {}) => {}
+            _ => panic!("expected InvalidBatchAuction error"),
+        }
+
+        let info = mock_info("creator", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("creator", &[]);
+        let msg = ExecuteMsg::SetMerkleRoot {
+            merkle_root: Some(root),
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        match res {
+            Err(ContractError::InvalidBatchAuction {}) => {}
+            _ => panic!("expected InvalidBatchAuction error"),
+        }
+
+        let info = mock_info("asdf", &[]);
+        let msg = ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+            amount: Uint128(100),
+            sender: "creator".to_string(),
+            msg: to_binary(&ReceiveMsg::Receive {}).unwrap(),
+        });
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("buyer", &coins(70, "token"));
+        let msg = ExecuteMsg::Buy {
+            prices: coins(7, "token"),
+            referrer: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        match res {
+            Err(ContractError::BatchAuctionOnly {}) => {}
+            _ => panic!("expected BatchAuctionOnly error"),
+        }
+
+        let info = mock_info("buyer", &coins(10, "ujuno"));
+        let msg = ExecuteMsg::Bid {
+            amount: Uint128(10),
+            max_price: Uint128(1),
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        match res {
+            Err(ContractError::BidBelowReserve { reserve_price }) => {
+                assert_eq!(Uint128(2), reserve_price)
+            }
+            _ => panic!("expected BidBelowReserve error"),
+        }
+
+        // anything above the escrow goes back
+        let info = mock_info("buyer", &coins(40, "ujuno"));
+        let msg = ExecuteMsg::Bid {
+            amount: Uint128(10),
+            max_price: Uint128(3),
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(res.attributes[3], attr("refunded", "10ujuno"));
+
+        // a new bid refunds the escrow of the one it replaces
+        let info = mock_info("buyer", &coins(40, "ujuno"));
+        let msg = ExecuteMsg::Bid {
+            amount: Uint128(20),
+            max_price: Uint128(2),
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(res.attributes[3], attr("refunded", "30ujuno"));
+
+        let info = mock_info("other", &coins(150, "ujuno"));
+        let msg = ExecuteMsg::Bid {
+            amount: Uint128(30),
+            max_price: Uint128(5),
+        };
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = QueryMsg::Bid {
+            address: "buyer".to_string(),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let value: BidResponse = from_binary(&res).unwrap();
+        assert_eq!(
+            Some(Bid {
+                amount: Uint128(20),
+                max_price: Uint128(2)
+            }),
+            value.bid
+        );
+
+        // the tokens on auction stay until it is finalized
+        let info = mock_info("creator", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::WithdrawAll {});
+        match res {
+            Err(ContractError::AuctionNotFinalized {}) => {}
+            _ => panic!("expected AuctionNotFinalized error"),
+        }
+
+        let mut env = mock_env();
+        env.block.time = end;
+        let info = mock_info("anyone", &[]);
+        let msg = ExecuteMsg::Finalize { limit: None };
+        let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        assert_eq!(res.attributes[2], attr("clearing_price", "2"));
+        assert_eq!(res.attributes[3], attr("sold", "50"));
+        assert_eq!(
+            res.messages,
+            vec![BankMsg::Send {
+                to_address: "creator".to_string(),
+                amount: coins(100, "ujuno"),
+            }
+            .into()]
+        );
+
+        let info = mock_info("anyone", &[]);
+        let msg = ExecuteMsg::Finalize { limit: None };
+        let res = execute(deps.as_mut(), env.clone(), info, msg);
+        match res {
+            Err(ContractError::AuctionFinalized {}) => {}
+            _ => panic!("expected AuctionFinalized error"),
+        }
+
+        // every bid is filled in full at the lowest price
+        let info = mock_info("other", &[]);
+        let res = execute(deps.as_mut(), env.clone(), info, ExecuteMsg::SettleBid {}).unwrap();
+        assert_eq!(res.attributes[1], attr("amount", "30"));
+        assert_eq!(res.attributes[2], attr("refunded", "90ujuno"));
+
+        let info = mock_info("creator", &[]);
+        let res = execute(deps.as_mut(), env, info, ExecuteMsg::WithdrawAll {}).unwrap();
+        assert_eq!(res.attributes[0], attr("amount", "50"));
+    }
+
+    #[test]
+    fn fair_launch_oversubscribed() {
+        let mut deps = mock_dependencies(&[]);
+        let end = mock_env().block.time.plus_seconds(100);
+
+        let mut msg = InstantiateMsg {
+            fair_launch: Some(FairLaunch {
+                denom: "ujuno".to_string(),
+                price: Uint128(2),
+            }),
+            ..sale_msg()
+        };
+
+        // deposits are only shared out once the sale ends
+        let info = mock_info("creator", &[]);
+        let res = instantiate(deps.as_mut(), mock_env(), info, msg.clone());
+        match res {
+            Err(ContractError::InvalidFairLaunch {}) => {}
+            _ => panic!("expected InvalidFairLaunch error"),
+        }
+
+        msg.end = Some(Expiration::AtTime(end));
+
+        // deposits are not checked against an allowlist
+        let root = "ab".repeat(32);
+        let info = mock_info("creator", &[]);
+        let allowlisted = InstantiateMsg {
+            merkle_root: Some(root.clone()),
+            ..msg.clone()
+        };
+        let res = instantiate(deps.as_mut(), mock_env(), info, allowlisted);
+        match res {
+            Err(ContractError::InvalidFairLaunch {}) => {}
+            _ => panic!("expected InvalidFairLaunch error"),
+        }
+
+        let info = mock_info("creator", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("creator", &[]);
+        let msg = ExecuteMsg::SetMerkleRoot {
+            merkle_root: Some(root),
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        match res {
+            Err(ContractError::InvalidFairLaunch {}) => {}
+            _ => panic!("expected InvalidFairLaunch error"),
+        }
+
+        let info = mock_info("asdf", &[]);
+        let msg = ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+            amount: Uint128(100),
+            sender: "creator".to_string(),
+            msg: to_binary(&ReceiveMsg::Receive {}).unwrap(),
+        });
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("buyer", &coins(70, "token"));
+        let msg = ExecuteMsg::Buy {
+            prices: coins(7, "token"),
+            referrer: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        match res {
+            Err(ContractError::FairLaunchOnly {}) => {}
+            _ => panic!("expected FairLaunchOnly error"),
+        }
+
+        let info = mock_info("alice", &coins(150, "token"));
+        let res = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Deposit {});
+        match res {
+            Err(ContractError::IncorretFunds {}) => {}
+            _ => panic!("expected IncorretFunds error"),
+        }
+
+        // 400 deposited for 100 tokens at 2 is oversubscribed twice over
+        for (depositor, amount) in [("alice", 150), ("bob", 150), ("carol", 100)] {
+            let info = mock_info(depositor, &coins(amount, "ujuno"));
+            let res = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Deposit {}).unwrap();
+            assert_eq!(res.attributes[1], attr("amount", amount.to_string()));
+        }
+
+        let info = mock_info("alice", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Claim {});
+        match res {
+            Err(ContractError::SaleNotEnded {}) => {}
+            _ => panic!("expected SaleNotEnded error"),
+        }
+
+        let mut env = mock_env();
+        env.block.time = end;
+
+        // the first settlement pays the owner for everything sold
+        let info = mock_info("alice", &[]);
+        let res = execute(deps.as_mut(), env.clone(), info, ExecuteMsg::Claim {}).unwrap();
+        assert_eq!(res.attributes[1], attr("amount", "37"));
+        assert_eq!(
+            res.messages[0],
+            BankMsg::Send {
+                to_address: "creator".to_string(),
+                amount: coins(200, "ujuno"),
+            }
+            .into()
+        );
+        assert_eq!(res.messages.len(), 2);
+
+        let info = mock_info("alice", &[]);
+        let res = execute(deps.as_mut(), env.clone(), info, ExecuteMsg::Claim {});
+        match res {
+            Err(ContractError::NothingToClaim {}) => {}
+            _ => panic!("expected NothingToClaim error"),
+        }
+
+        // rounding is settled against the running total, so every token is claimed
+        let info = mock_info("bob", &[]);
+        let res = execute(deps.as_mut(), env.clone(), info, ExecuteMsg::Claim {}).unwrap();
+        assert_eq!(res.attributes[1], attr("amount", "38"));
+        assert_eq!(res.messages.len(), 1);
+        let info = mock_info("carol", &[]);
+        let res = execute(deps.as_mut(), env.clone(), info, ExecuteMsg::Claim {}).unwrap();
+        assert_eq!(res.attributes[1], attr("amount", "25"));
+
+        for (depositor, refunded) in [
+            ("carol", "50ujuno"),
+            ("alice", "75ujuno"),
+            ("bob", "75ujuno"),
+        ] {
+            let info = mock_info(depositor, &[]);
+            let res = execute(deps.as_mut(), env.clone(), info, ExecuteMsg::Refund {}).unwrap();
+            assert_eq!(res.attributes[1], attr("refunded", refunded));
+        }
+
+        let info = mock_info("alice", &[]);
+        let res = execute(deps.as_mut(), env.clone(), info, ExecuteMsg::Refund {});
+        match res {
+            Err(ContractError::NothingToRefund {}) => {}
+            _ => panic!("expected NothingToRefund error"),
+        }
+
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::FairLaunch {}).unwrap();
+        let value: FairLaunchResponse = from_binary(&res).unwrap();
+        assert_eq!(Uint128(400), value.deposits);
+        assert_eq!(Some(Uint128(100)), value.sold);
+        assert_eq!(Uint128(200), value.raised);
+
+        let msg = QueryMsg::Deposit {
+            address: "alice".to_string(),
+        };
+        let res = query(deps.as_ref(), env, msg).unwrap();
+        let value: DepositResponse = from_binary(&res).unwrap();
+        assert_eq!(Uint128(150), value.deposit);
+        assert!(value.claimed && value.refunded);
+    }
+
+    #[test]
+    fn soft_cap_missed() {
+        let mut deps = mock_dependencies(&[]);
+        let end = mock_env().block.time.plus_seconds(100);
+
+        let mut msg = InstantiateMsg {
+            soft_cap: Some(Uint128(50)),
+            ..sale_msg()
+        };
+
+        let info = mock_info("creator", &[]);
+        let res = instantiate(deps.as_mut(), mock_env(), info, msg.clone());
+        match res {
+            Err(ContractError::InvalidSoftCap {}) => {}
+            _ => panic!("expected InvalidSoftCap error"),
+        }
+
+        msg.end = Some(Expiration::AtTime(end));
+        let info = mock_info("creator", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("asdf", &[]);
+        let msg = ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+            amount: Uint128(100),
+            sender: "creator".to_string(),
+            msg: to_binary(&ReceiveMsg::Receive {}).unwrap(),
+        });
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // both the payment and the tokens stay in the contract
+        let info = mock_info("buyer", &coins(70, "token"));
+        let msg = ExecuteMsg::Buy {
+            prices: coins(7, "token"),
+            referrer: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(res.attributes[0], attr("amount", "10"));
+        assert!(res.messages.is_empty());
+
+        let info = mock_info("buyer", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Refund {});
+        match res {
+            Err(ContractError::SaleNotEnded {}) => {}
+            _ => panic!("expected SaleNotEnded error"),
+        }
+
+        let mut env = mock_env();
+        env.block.time = end;
+
+        let info = mock_info("buyer", &[]);
+        let res = execute(deps.as_mut(), env.clone(), info, ExecuteMsg::Claim {});
+        match res {
+            Err(ContractError::SoftCapNotReached {}) => {}
+            _ => panic!("expected SoftCapNotReached error"),
+        }
+
+        let info = mock_info("buyer", &[]);
+        let res = execute(deps.as_mut(), env.clone(), info, ExecuteMsg::Refund {}).unwrap();
+        assert_eq!(res.attributes[1], attr("refunded", "70token"));
+        assert_eq!(
+            res.messages,
+            vec![BankMsg::Send {
+                to_address: "buyer".to_string(),
+                amount: coins(70, "token"),
+            }
+            .into()]
+        );
+
+        let info = mock_info("buyer", &[]);
+        let res = execute(deps.as_mut(), env.clone(), info, ExecuteMsg::Refund {});
+        match res {
+            Err(ContractError::NothingToRefund {}) => {}
+            _ => panic!("expected NothingToRefund error"),
+        }
+
+        // the refunded tokens are no longer owed to the buyer
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::GetInfo {}).unwrap();
+        let value: InfoResponse = from_binary(&res).unwrap();
+        assert_eq!(Uint128(100), value.balance);
+        assert_eq!(Uint128(0), value.tokens_sold);
+        assert_eq!(Some(Uint128(0)), OWED.may_load(&deps.storage).unwrap());
+
+        // the owner gets back every token, including those bought
+        let info = mock_info("creator", &[]);
+        let res = execute(deps.as_mut(), env, info, ExecuteMsg::WithdrawAll {}).unwrap();
+        assert_eq!(res.attributes[0], attr("amount", "100"));
+    }
+
+    #[test]
+    fn escrow_proceeds() {
+        let mut deps = mock_dependencies(&[]);
+
+        let msg = InstantiateMsg {
+            escrow_proceeds: true,
+            ..sale_msg()
+        };
+        let info = mock_info("creator", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("asdf", &[]);
+        let msg = ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+            amount: Uint128(100),
+            sender: "creator".to_string(),
+            msg: to_binary(&ReceiveMsg::Receive {}).unwrap(),
+        });
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // payment cw20s are escrowed too, keyed by their address
+        let info = mock_info("creator", &[]);
+        let msg = ExecuteMsg::SetCw20Price {
+            address: "cash".to_string(),
+            price: Uint128(7),
+        };
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let info = mock_info("cash", &[]);
+        let msg = ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+            amount: Uint128(14),
+            sender: "buyer".to_string(),
+            msg: to_binary(&ReceiveMsg::Buy { price: Uint128(7) }).unwrap(),
+        });
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(res.messages.len(), 1);
+
+        // only the tokens leave the contract
+        let info = mock_info("buyer", &coins(70, "token"));
+        let msg = ExecuteMsg::Buy {
+            prices: coins(7, "token"),
+            referrer: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(res.messages.len(), 1);
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Proceeds {}).unwrap();
+        let value: ProceedsResponse = from_binary(&res).unwrap();
+        assert_eq!(coins(70, "token"), value.proceeds);
+        assert_eq!(
+            vec![Cw20Coin {
+                address: "cash".to_string(),
+                amount: Uint128(14),
+            }],
+            value.cw20_proceeds
+        );
+
+        let info = mock_info("buyer", &[]);
+        let msg = ExecuteMsg::WithdrawProceeds {
+            denom: "token".to_string(),
+            amount: None,
+            recipient: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        match res {
+            Err(ContractError::Unauthorized {}) => {}
+            _ => panic!("Must return unauthorized error"),
+        }
+
+        let info = mock_info("creator", &[]);
+        let msg = ExecuteMsg::WithdrawProceeds {
+            denom: "token".to_string(),
+            amount: Some(Uint128(100)),
+            recipient: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        match res {
+            Err(ContractError::InsufficientProceeds { available }) => {
+                assert_eq!(Uint128(70), available)
+            }
+            _ => panic!("expected InsufficientProceeds error"),
+        }
+
+        let info = mock_info("creator", &[]);
+        let msg = ExecuteMsg::WithdrawProceeds {
+            denom: "token".to_string(),
+            amount: Some(Uint128(30)),
+            recipient: Some("treasury".to_string()),
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![BankMsg::Send {
+                to_address: "treasury".to_string(),
+                amount: coins(30, "token"),
+            }
+            .into()]
+        );
+
+        // the rest goes to the owner by default
+        let info = mock_info("creator", &[]);
+        let msg = ExecuteMsg::WithdrawProceeds {
+            denom: "token".to_string(),
+            amount: None,
+            recipient: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(res.attributes[1], attr("amount", "40token"));
+        assert_eq!(
+            res.messages,
+            vec![BankMsg::Send {
+                to_address: "creator".to_string(),
+                amount: coins(40, "token"),
+            }
+            .into()]
+        );
+
+        let info = mock_info("creator", &[]);
+        let msg = ExecuteMsg::WithdrawProceeds {
+            denom: "cash".to_string(),
+            amount: None,
+            recipient: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(res.attributes[1], attr("amount", "14cash"));
+        assert_eq!(
+            res.messages,
+            vec![cw20_transfer_msg(
+                &Addr::unchecked("cash"),
+                &Addr::unchecked("creator"),
+                Uint128(14)
+            )
+            .unwrap()]
+        );
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Proceeds {}).unwrap();
+        let value: ProceedsResponse = from_binary(&res).unwrap();
+        assert!(value.proceeds.is_empty());
+        assert!(value.cw20_proceeds.is_empty());
+    }
+
+    #[test]
+    fn escrow_settled_proceeds() {
+        let end = mock_env().block.time.plus_seconds(100);
+        let mut env = mock_env();
+        env.block.time = end;
+        let fair_launch = InstantiateMsg {
+            end: Some(Expiration::AtTime(end)),
+            fair_launch: Some(FairLaunch {
+                denom: "ujuno".to_string(),
+                price: Uint128(2),
+            }),
+            escrow_proceeds: true,
+            ..sale_msg()
+        };
+        let batch_auction = InstantiateMsg {
+            end: Some(Expiration::AtTime(end)),
+            batch_auction: Some(BatchAuction {
+                denom: "ujuno".to_string(),
+                reserve_price: Uint128(2),
+            }),
+            escrow_proceeds: true,
+            ..sale_msg()
+        };
+        let sales = vec![
+            (fair_launch, ExecuteMsg::Deposit {}, ExecuteMsg::Claim {}),
+            (
+                batch_auction,
+                ExecuteMsg::Bid {
+                    amount: Uint128(20),
+                    max_price: Uint128(2),
+                },
+                ExecuteMsg::Finalize { limit: None },
+            ),
+        ];
+
+        // settling the sale keeps what was raised until it is withdrawn
+        for (msg, buy, settle) in sales {
+            let mut deps = mock_dependencies(&[]);
+            let info = mock_info("creator", &[]);
+            let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+            let info = mock_info("asdf", &[]);
+            let msg = ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+                amount: Uint128(100),
+                sender: "creator".to_string(),
+                msg: to_binary(&ReceiveMsg::Receive {}).unwrap(),
+            });
+            let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+            let info = mock_info("buyer", &coins(40, "ujuno"));
+            let _res = execute(deps.as_mut(), mock_env(), info, buy).unwrap();
+
+            let info = mock_info("buyer", &[]);
+            let res = execute(deps.as_mut(), env.clone(), info, settle).unwrap();
+            assert!(res.messages.iter().all(|msg| !matches!(
+                msg,
+                CosmosMsg::Bank(BankMsg::Send { to_address, .. }) if to_address == "creator"
+            )));
+
+            let res = query(deps.as_ref(), env.clone(), QueryMsg::Proceeds {}).unwrap();
+            let value: ProceedsResponse = from_binary(&res).unwrap();
+            assert_eq!(coins(40, "ujuno"), value.proceeds);
+        }
+    }
+
+    #[test]
+    fn proceeds_split() {
+        let mut deps = mock_dependencies(&[]);
+
+        let recipient = |address: &str, weight: u64| Recipient {
+            address: Addr::unchecked(address),
+            weight,
+        };
+        let mut msg = InstantiateMsg {
+            split: Some(ProceedsSplit {
+                recipients: vec![recipient("treasury", 5), recipient("dev", 0)],
+                remainder: Addr::unchecked("treasury"),
+            }),
+            ..sale_msg()
+        };
+
+        let info = mock_info("creator", &[]);
+        let res = instantiate(deps.as_mut(), mock_env(), info, msg.clone());
+        match res {
+            Err(ContractError::InvalidProceedsSplit {}) => {}
+            _ => panic!("expected InvalidProceedsSplit error"),
+        }
+
+        msg.split = Some(ProceedsSplit {
+            recipients: vec![
+                recipient("treasury", 5),
+                recipient("dev", 3),
+                recipient("marketing", 2),
+            ],
+            remainder: Addr::unchecked("treasury"),
+        });
+        let info = mock_info("creator", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("asdf", &[]);
+        let msg = ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+            amount: Uint128(100),
+            sender: "creator".to_string(),
+            msg: to_binary(&ReceiveMsg::Receive {}).unwrap(),
+        });
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // 77 splits 38 / 23 / 15, the 1 left over goes to the treasury
+        let info = mock_info("buyer", &coins(77, "token"));
+        let msg = ExecuteMsg::Buy {
+            prices: coins(7, "token"),
+            referrer: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap();
+        let payouts: Vec<CosmosMsg> = [("treasury", 39), ("dev", 23), ("marketing", 15)]
+            .iter()
+            .map(|(address, amount)| {
+                BankMsg::Send {
+                    to_address: address.to_string(),
+                    amount: coins(*amount, "token"),
+                }
+                .into()
+            })
+            .collect();
+        assert_eq!(res.messages[1..], payouts[..]);
+
+        let info = mock_info("buyer", &[]);
+        let split_msg = ExecuteMsg::SetProceedsSplit { split: None };
+        let res = execute(deps.as_mut(), mock_env(), info, split_msg.clone());
+        match res {
+            Err(ContractError::Unauthorized {}) => {}
+            _ => panic!("Must return unauthorized error"),
+        }
+
+        // without a split everything goes to the owner again
+        let info = mock_info("creator", &[]);
+        let _res = execute(deps.as_mut(), mock_env(), info, split_msg).unwrap();
+        let info = mock_info("buyer", &coins(77, "token"));
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(
+            res.messages[1],
+            BankMsg::Send {
+                to_address: "creator".to_string(),
+                amount: coins(77, "token"),
+            }
+            .into()
+        );
+    }
+
+    #[test]
+    fn platform_fee() {
+        let mut deps = mock_dependencies(&[]);
+
+        let mut msg = InstantiateMsg {
+            fee: Some(Fee {
+                collector: Addr::unchecked("launchpad"),
+                bps: 10_001,
+            }),
+            ..sale_msg()
+        };
+
+        let info = mock_info("creator", &[]);
+        let res = instantiate(deps.as_mut(), mock_env(), info, msg.clone());
+        match res {
+            Err(ContractError::InvalidFee {}) => {}
+            _ => panic!("expected InvalidFee error"),
+        }
+
+        msg.fee = Some(Fee {
+            collector: Addr::unchecked("launchpad"),
+            bps: 250,
+        });
+        let info = mock_info("creator", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("asdf", &[]);
+        let msg = ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+            amount: Uint128(100),
+            sender: "creator".to_string(),
+            msg: to_binary(&ReceiveMsg::Receive {}).unwrap(),
+        });
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // 2.5% of 77 rounds down to 1
+        let info = mock_info("buyer", &coins(77, "token"));
+        let msg = ExecuteMsg::Buy {
+            prices: coins(7, "token"),
+            referrer: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap();
+        assert_eq!(res.attributes[3], attr("fee", "1token"));
+        let payouts: Vec<CosmosMsg> = [("launchpad", 1), ("creator", 76)]
+            .iter()
+            .map(|(address, amount)| {
+                BankMsg::Send {
+                    to_address: address.to_string(),
+                    amount: coins(*amount, "token"),
+                }
+                .into()
+            })
+            .collect();
+        assert_eq!(res.messages[1..], payouts[..]);
+
+        // only the collector can change the fee
+        let fee_msg = ExecuteMsg::SetFee {
+            bps: 1_000,
+            collector: Some("treasury".to_string()),
+        };
+        let info = mock_info("creator", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, fee_msg.clone());
+        match res {
+            Err(ContractError::Unauthorized {}) => {}
+            _ => panic!("Must return unauthorized error"),
+        }
+
+        let info = mock_info("launchpad", &[]);
+        let _res = execute(deps.as_mut(), mock_env(), info, fee_msg).unwrap();
+
+        let info = mock_info("buyer", &coins(70, "token"));
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(res.attributes[3], attr("fee", "7token"));
+        assert_eq!(
+            res.messages[1],
+            BankMsg::Send {
+                to_address: "treasury".to_string(),
+                amount: coins(7, "token"),
+            }
+            .into()
+        );
+
+        let info = mock_info("launchpad", &[]);
+        let msg = ExecuteMsg::SetFee {
+            bps: 1_000,
+            collector: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        match res {
+            Err(ContractError::Unauthorized {}) => {}
+            _ => panic!("Must return unauthorized error"),
+        }
+    }
+
+    #[test]
+    fn escrowed_fee_fixed_at_purchase() {
+        let end = mock_env().block.time.plus_seconds(100);
+        let mut env = mock_env();
+        env.block.time = end;
+        let fee = Some(Fee {
+            collector: Addr::unchecked("launchpad"),
+            bps: 1_000,
+        });
+        let escrowed = InstantiateMsg {
+            escrow_proceeds: true,
+            fee: fee.clone(),
+            ..sale_msg()
+        };
+        let soft_capped = InstantiateMsg {
+            end: Some(Expiration::AtTime(end)),
+            soft_cap: Some(Uint128(10)),
+            fee,
+            ..sale_msg()
+        };
+
+        // the fee is paid with the purchase, or set aside until the soft cap is met
+        for (msg, fee_msgs) in [(escrowed, 1), (soft_capped, 0)] {
+            let mut deps = mock_dependencies(&[]);
+            let info = mock_info("creator", &[]);
+            let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+            let info = mock_info("asdf", &[]);
+            let msg = ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+                amount: Uint128(100),
+                sender: "creator".to_string(),
+                msg: to_binary(&ReceiveMsg::Receive {}).unwrap(),
+            });
+            let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+            let info = mock_info("buyer", &coins(70, "token"));
+            let msg = ExecuteMsg::Buy {
+                prices: coins(7, "token"),
+                referrer: None,
+            };
+            let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+            assert_eq!(res.attributes[3], attr("fee", "7token"));
+            let fee_msg: CosmosMsg = BankMsg::Send {
+                to_address: "launchpad".to_string(),
+                amount: coins(7, "token"),
+            }
+            .into();
+            let paid = res.messages.iter().filter(|msg| **msg == fee_msg).count();
+            assert_eq!(fee_msgs, paid);
+
+            let res = query(deps.as_ref(), mock_env(), QueryMsg::Proceeds {}).unwrap();
+            let value: ProceedsResponse = from_binary(&res).unwrap();
+            assert_eq!(coins(63, "token"), value.proceeds);
+
+            // raising the fee later leaves what was already bought alone
+            let info = mock_info("launchpad", &[]);
+            let msg = ExecuteMsg::SetFee {
+                bps: 10_000,
+                collector: None,
+            };
+            let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+            let info = mock_info("creator", &[]);
+            let msg = ExecuteMsg::WithdrawProceeds {
+                denom: "token".to_string(),
+                amount: None,
+                recipient: None,
+            };
+            let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+            let mut payouts = vec![fee_msg; 1 - fee_msgs];
+            payouts.push(
+                BankMsg::Send {
+                    to_address: "creator".to_string(),
+                    amount: coins(63, "token"),
+                }
+                .into(),
+            );
+            assert_eq!(res.messages, payouts);
+        }
+    }
+
+    #[test]
+    fn release_fees_without_withdrawal() {
+        let mut deps = mock_dependencies(&[]);
+        let end = mock_env().block.time.plus_seconds(100);
+
+        let msg = InstantiateMsg {
+            end: Some(Expiration::AtTime(end)),
+            soft_cap: Some(Uint128(10)),
+            fee: Some(Fee {
+                collector: Addr::unchecked("launchpad"),
+                bps: 1_000,
+            }),
+            ..sale_msg()
+        };
+        let info = mock_info("creator", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("asdf", &[]);
+        let msg = ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+            amount: Uint128(100),
+            sender: "creator".to_string(),
+            msg: to_binary(&ReceiveMsg::Receive {}).unwrap(),
+        });
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("buyer", &coins(70, "token"));
+        let msg = ExecuteMsg::Buy {
+            prices: coins(7, "token"),
+            referrer: None,
+        };
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // the fee stays refundable until the sale ends
+        let info = mock_info("anyone", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::ReleaseFees {});
+        match res {
+            Err(ContractError::SaleNotEnded {}) => {}
+            _ => panic!("expected SaleNotEnded error"),
+        }
+
+        // anyone can pay the collector without the owner withdrawing
+        let mut env = mock_env();
+        env.block.time = end;
+        let info = mock_info("anyone", &[]);
+        let res = execute(deps.as_mut(), env.clone(), info, ExecuteMsg::ReleaseFees {}).unwrap();
+        assert_eq!(res.attributes[1], attr("fee", "7token"));
+        assert_eq!(
+            res.messages,
+            vec![BankMsg::Send {
+                to_address: "launchpad".to_string(),
+                amount: coins(7, "token"),
+            }
+            .into()]
+        );
+
+        let info = mock_info("anyone", &[]);
+        let res = execute(deps.as_mut(), env.clone(), info, ExecuteMsg::ReleaseFees {});
+        match res {
+            Err(ContractError::NothingToClaim {}) => {}
+            _ => panic!("expected NothingToClaim error"),
+        }
+
+        // the owner still withdraws the proceeds, without the fee
+        let info = mock_info("creator", &[]);
+        let msg = ExecuteMsg::WithdrawProceeds {
+            denom: "token".to_string(),
+            amount: None,
+            recipient: None,
+        };
+        let res = execute(deps.as_mut(), env, info, msg).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![BankMsg::Send {
+                to_address: "creator".to_string(),
+                amount: coins(63, "token"),
+            }
+            .into()]
+        );
+    }
+
+    #[test]
+    fn soft_cap_missed_refunds_fee() {
+        let mut deps = mock_dependencies(&[]);
+        let end = mock_env().block.time.plus_seconds(100);
+
+        let msg = InstantiateMsg {
+            end: Some(Expiration::AtTime(end)),
+            soft_cap: Some(Uint128(50)),
+            fee: Some(Fee {
+                collector: Addr::unchecked("launchpad"),
+                bps: 1_000,
+            }),
+            ..sale_msg()
+        };
+        let info = mock_info("creator", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("asdf", &[]);
+        let msg = ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+            amount: Uint128(100),
+            sender: "creator".to_string(),
+            msg: to_binary(&ReceiveMsg::Receive {}).unwrap(),
+        });
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        for (buyer, amount) in [("alice", 70), ("bob", 140)] {
+            let info = mock_info(buyer, &coins(amount, "token"));
+            let msg = ExecuteMsg::Buy {
+                prices: coins(7, "token"),
+                referrer: None,
+            };
+            let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+            assert!(res.messages.is_empty());
+        }
+
+        // every buyer gets back the whole payment, fee included
+        let mut env = mock_env();
+        env.block.time = end;
+        for (buyer, refunded) in [("bob", "140token"), ("alice", "70token")] {
+            let info = mock_info(buyer, &[]);
+            let res = execute(deps.as_mut(), env.clone(), info, ExecuteMsg::Refund {}).unwrap();
+            assert_eq!(res.attributes[1], attr("refunded", refunded));
+        }
+        assert_eq!(
+            Some(Uint128(0)),
+            FEES.may_load(&deps.storage, "token").unwrap()
+        );
+
+        let res = query(deps.as_ref(), env, QueryMsg::Proceeds {}).unwrap();
+        let value: ProceedsResponse = from_binary(&res).unwrap();
+        assert!(value.proceeds.is_empty());
+    }
+
+    #[test]
+    fn soft_cap_missed_refunds_whole_fee() {
+        let mut deps = mock_dependencies(&[]);
+        let end = mock_env().block.time.plus_seconds(100);
+
+        let msg = InstantiateMsg {
+            end: Some(Expiration::AtTime(end)),
+            soft_cap: Some(Uint128(50)),
+            fee: Some(Fee {
+                collector: Addr::unchecked("launchpad"),
+                bps: 10_000,
+            }),
+            ..sale_msg()
+        };
+        let info = mock_info("creator", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("asdf", &[]);
+        let msg = ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+            amount: Uint128(100),
+            sender: "creator".to_string(),
+            msg: to_binary(&ReceiveMsg::Receive {}).unwrap(),
+        });
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // the fee takes the whole payment, nothing is left in the proceeds
+        let info = mock_info("buyer", &coins(70, "token"));
+        let msg = ExecuteMsg::Buy {
+            prices: coins(7, "token"),
+            referrer: None,
+        };
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let mut env = mock_env();
+        env.block.time = end;
+        let info = mock_info("buyer", &[]);
+        let res = execute(deps.as_mut(), env, info, ExecuteMsg::Refund {}).unwrap();
+        assert_eq!(res.attributes[1], attr("refunded", "70token"));
+        assert_eq!(
+            Some(Uint128(0)),
+            FEES.may_load(&deps.storage, "token").unwrap()
+        );
+    }
+
+    #[test]
+    fn referral_rewards() {
+        let mut deps = mock_dependencies(&[]);
+
+        let msg = InstantiateMsg {
+            referral: Some(Referral {
+                bps: 1_000,
+                reward: ReferralReward::Proceeds,
+            }),
+            ..sale_msg()
+        };
+        let info = mock_info("creator", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("asdf", &[]);
+        let msg = ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+            amount: Uint128(100),
+            sender: "creator".to_string(),
+            msg: to_binary(&ReceiveMsg::Receive {}).unwrap(),
+        });
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("buyer", &coins(70, "token"));
+        let msg = ExecuteMsg::Buy {
+            prices: coins(7, "token"),
+            referrer: Some("buyer".to_string()),
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        match res {
+            Err(ContractError::SelfReferral {}) => {}
+            _ => panic!("expected SelfReferral error"),
+        }
+
+        // the referrer takes 10% of the payment
+        let msg = ExecuteMsg::Buy {
+            prices: coins(7, "token"),
+            referrer: Some("friend".to_string()),
+        };
+        for _ in 0..2 {
+            let info = mock_info("buyer", &coins(70, "token"));
+            let res = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap();
+            let payouts: Vec<CosmosMsg> = [("friend", 7), ("creator", 63)]
+                .iter()
+                .map(|(address, amount)| {
+                    BankMsg::Send {
+                        to_address: address.to_string(),
+                        amount: coins(*amount, "token"),
+                    }
+                    .into()
+                })
+                .collect();
+            assert_eq!(res.messages[1..], payouts[..]);
+        }
+
+        let msg = QueryMsg::ReferralStats {
+            address: "friend".to_string(),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let value: ReferralStatsResponse = from_binary(&res).unwrap();
+        assert_eq!(2, value.referrals);
+        assert_eq!(Uint128(20), value.volume);
+    }
+
+    #[test]
+    fn referral_bonus_within_hard_cap() {
+        let mut deps = mock_dependencies(&[]);
+
+        let msg = InstantiateMsg {
+            hard_cap: Some(Uint128(21)),
+            referral: Some(Referral {
+                bps: 1_000,
+                reward: ReferralReward::Tokens,
+            }),
+            ..sale_msg()
+        };
+        let info = mock_info("creator", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("asdf", &[]);
+        let msg = ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+            amount: Uint128(100),
+            sender: "creator".to_string(),
+            msg: to_binary(&ReceiveMsg::Receive {}).unwrap(),
+        });
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // the bonus is sold too
+        let msg = ExecuteMsg::Buy {
+            prices: coins(7, "token"),
+            referrer: Some("friend".to_string()),
+        };
+        let info = mock_info("buyer", &coins(70, "token"));
+        let _res = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap();
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetInfo {}).unwrap();
+        let value: InfoResponse = from_binary(&res).unwrap();
+        assert_eq!(Uint128(11), value.tokens_sold);
+        assert_eq!(Uint128(89), value.balance);
+
+        // 10 more tokens fit under the cap, but not with their bonus
+        let info = mock_info("buyer", &coins(70, "token"));
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        match res {
+            Err(ContractError::HardCapExceeded { remaining }) => {
+                assert_eq!(Uint128(0), remaining)
+            }
+            _ => panic!("expected HardCapExceeded error"),
+        }
+    }
+
+    #[test]
+    fn withdraw_cw20_token() {
+        let mut deps = mock_dependencies(&coins(2, "token"));
+
+        let msg = sale_msg();
+        let info = mock_info("creator", &coins(2, "token"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("asdf", &[]);
+        let msg = ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+            amount: Uint128(10),
+            sender: "asdf".to_string(),
+            msg: to_binary(&ReceiveMsg::Receive {}).unwrap(),
+        });
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // check balance
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetInfo {}).unwrap();
+        let value: InfoResponse = from_binary(&res).unwrap();
+        assert_eq!(Uint128(10), value.balance);
+
+        let info = mock_info("creator", &coins(2, "token"));
+        let msg = ExecuteMsg::WithdrawAll {};
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // check balance
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetInfo {}).unwrap();
+        let value: InfoResponse = from_binary(&res).unwrap();
+        assert_eq!(Uint128(0), value.balance);
+    }
+
+    #[test]
+    fn withdraw_part_of_cw20_token() {
+        let mut deps = mock_dependencies(&[]);
+
+        let msg = sale_msg();
+        let info = mock_info("creator", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("asdf", &[]);
+        let msg = ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+            amount: Uint128(10),
+            sender: "creator".to_string(),
+            msg: to_binary(&ReceiveMsg::Receive {}).unwrap(),
+        });
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // only the owner can withdraw, and no more than the balance
+        let msg = ExecuteMsg::Withdraw {
+            amount: Uint128(4),
+            recipient: Some("treasury".to_string()),
+            msg: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), msg);
+        assert!(matches!(res, Err(ContractError::Unauthorized {})));
+        let msg = ExecuteMsg::Withdraw {
+            amount: Uint128(11),
+            recipient: None,
+            msg: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg);
+        match res {
+            Err(ContractError::InsufficientBalance { available }) => {
+                assert_eq!(available, Uint128(10))
+            }
+            _ => panic!("expected InsufficientBalance error"),
+        }
+
+        let msg = ExecuteMsg::Withdraw {
+            amount: Uint128(4),
+            recipient: Some("treasury".to_string()),
+            msg: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![cw20_transfer_msg(
+                &Addr::unchecked("asdf"),
+                &Addr::unchecked("treasury"),
+                Uint128(4)
+            )
+            .unwrap()]
+        );
+
+        // a payload sends the tokens through the recipient's receive hook
+        let payload = to_binary("stake").unwrap();
+        let msg = ExecuteMsg::Withdraw {
+            amount: Uint128(5),
+            recipient: Some("staking".to_string()),
+            msg: Some(payload.clone()),
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "asdf".to_string(),
+                msg: to_binary(&Cw20ExecuteMsg::Send {
+                    contract: "staking".to_string(),
+                    amount: Uint128(5),
+                    msg: Some(payload),
+                })
+                .unwrap(),
+                send: vec![],
+            })]
+        );
+
+        // the rest stays on sale
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetInfo {}).unwrap();
+        let value: InfoResponse = from_binary(&res).unwrap();
+        assert_eq!(Uint128(1), value.balance);
+        let info = mock_info("buyer", &coins(7, "token"));
+        let msg = ExecuteMsg::Buy {
+            prices: coins(7, "token"),
+            referrer: None,
+        };
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    }
+
+    #[test]
+    fn pause_and_unpause() {
+        let mut deps = mock_dependencies(&[]);
+
+        let msg = InstantiateMsg {
+            price: Some(Uint128::from(1u128)),
+            guardian: Some(Addr::unchecked("guardian")),
+            ..sale_msg()
+        };
+        let info = mock_info("creator", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("asdf", &[]);
+        let deposit = ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+            amount: Uint128(10),
+            sender: "creator".to_string(),
+            msg: to_binary(&ReceiveMsg::Receive {}).unwrap(),
+        });
+        let _res = execute(deps.as_mut(), mock_env(), info.clone(), deposit.clone()).unwrap();
+
+        // only the owner or the guardian can pause
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            ExecuteMsg::Pause {},
+        );
+        assert!(matches!(res, Err(ContractError::Unauthorized {})));
+        let _res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("guardian", &[]),
+            ExecuteMsg::Pause {},
+        )
+        .unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetInfo {}).unwrap();
+        let value: InfoResponse = from_binary(&res).unwrap();
+        assert!(value.paused);
+
+        // buying and the receive hook are refused while paused
+        let buy = ExecuteMsg::Buy {
+            prices: coins(1, "token"),
+            referrer: None,
+        };
+        let buyer = mock_info("buyer", &coins(2, "token"));
+        let res = execute(deps.as_mut(), mock_env(), buyer.clone(), buy.clone());
+        assert!(matches!(res, Err(ContractError::Paused {})));
+        let res = execute(deps.as_mut(), mock_env(), info, deposit);
+        assert!(matches!(res, Err(ContractError::Paused {})));
+
+        // the guardian cannot unpause or withdraw
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("guardian", &[]),
+            ExecuteMsg::Unpause {},
+        );
+        assert!(matches!(res, Err(ContractError::Unauthorized {})));
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("guardian", &[]),
+            ExecuteMsg::WithdrawAll {},
+        );
+        assert!(matches!(res, Err(ContractError::Unauthorized {})));
+
+        let _res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            ExecuteMsg::Unpause {},
+        )
+        .unwrap();
+        let _res = execute(deps.as_mut(), mock_env(), buyer, buy).unwrap();
+
+        // removing the guardian leaves only the owner able to pause
+        let msg = ExecuteMsg::SetGuardian { guardian: None };
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("guardian", &[]),
+            msg.clone(),
+        );
+        assert!(matches!(res, Err(ContractError::Unauthorized {})));
+        let _res = execute(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("guardian", &[]),
+            ExecuteMsg::Pause {},
+        );
+        assert!(matches!(res, Err(ContractError::Unauthorized {})));
+    }
+
+    #[test]
+    fn pause_fair_launch_deposits() {
+        let mut deps = mock_dependencies(&[]);
+
+        let msg = InstantiateMsg {
+            end: Some(Expiration::AtTime(mock_env().block.time.plus_seconds(100))),
+            fair_launch: Some(FairLaunch {
+                denom: "ujuno".to_string(),
+                price: Uint128(2),
+            }),
+            ..sale_msg()
+        };
+        let info = mock_info("creator", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("asdf", &[]);
+        let msg = ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+            amount: Uint128(100),
+            sender: "creator".to_string(),
+            msg: to_binary(&ReceiveMsg::Receive {}).unwrap(),
+        });
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("creator", &[]);
+        let _res = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Pause {}).unwrap();
+
+        let info = mock_info("alice", &coins(10, "ujuno"));
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::Deposit {},
+        );
+        assert!(matches!(res, Err(ContractError::Paused {})));
+
+        let creator = mock_info("creator", &[]);
+        let _res = execute(deps.as_mut(), mock_env(), creator, ExecuteMsg::Unpause {}).unwrap();
+        let _res = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Deposit {}).unwrap();
+    }
+
+    #[test]
+    fn pause_batch_auction_bids() {
+        let mut deps = mock_dependencies(&[]);
+
+        let msg = InstantiateMsg {
+            end: Some(Expiration::AtTime(mock_env().block.time.plus_seconds(100))),
+            batch_auction: Some(BatchAuction {
+                denom: "ujuno".to_string(),
+                reserve_price: Uint128(2),
+            }),
+            ..sale_msg()
+        };
+        let info = mock_info("creator", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("asdf", &[]);
+        let msg = ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+            amount: Uint128(100),
+            sender: "creator".to_string(),
+            msg: to_binary(&ReceiveMsg::Receive {}).unwrap(),
+        });
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("creator", &[]);
+        let _res = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Pause {}).unwrap();
+
+        let info = mock_info("alice", &coins(20, "ujuno"));
+        let bid = ExecuteMsg::Bid {
+            amount: Uint128(10),
+            max_price: Uint128(2),
+        };
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), bid.clone());
+        assert!(matches!(res, Err(ContractError::Paused {})));
+
+        let creator = mock_info("creator", &[]);
+        let _res = execute(deps.as_mut(), mock_env(), creator, ExecuteMsg::Unpause {}).unwrap();
+        let _res = execute(deps.as_mut(), mock_env(), info, bid).unwrap();
+    }
+
+    #[test]
+    fn withdraw_cw20_token_only_creator() {
+        let mut deps = mock_dependencies(&coins(2, "token"));
+
+        let msg = sale_msg();
+        let info = mock_info("creator", &coins(2, "token"));
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("imposter", &coins(2, "token"));
+
+        let msg = ExecuteMsg::WithdrawAll {};
+        let _res = execute(deps.as_mut(), mock_env(), info, msg);
+        assert!(_res.is_err());
+    }
+
+    #[test]
+    fn transfer_ownership() {
+        let mut deps = mock_dependencies(&[]);
+
+        let msg = sale_msg();
+        let info = mock_info("creator", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // non-owner cannot propose
+        let info = mock_info("imposter", &[]);
+        let msg = ExecuteMsg::ProposeNewOwner {
+            owner: "imposter".to_string(),
+            expiry: None,
+        };
+        let _res = execute(deps.as_mut(), mock_env(), info, msg);
+        assert!(_res.is_err());
+
+        // nothing to accept yet
+        let info = mock_info("dao", &[]);
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::AcceptOwnership {},
+        );
+        match res {
+            Err(ContractError::NoPendingOwner {}) => {}
+            _ => panic!("expected NoPendingOwner error"),
+        }
+
+        let info = mock_info("creator", &[]);
+        let msg = ExecuteMsg::ProposeNewOwner {
+            owner: "dao".to_string(),
+            expiry: None,
+        };
+        let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::PendingOwner {}).unwrap();
+        let value: PendingOwnerResponse = from_binary(&res).unwrap();
+        assert_eq!(Some(Addr::unchecked("dao")), value.pending_owner);
+        assert_eq!(None, value.expiry);
+
+        // only the proposed owner can accept
+        let info = mock_info("imposter", &[]);
+        let _res = execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::AcceptOwnership {},
+        );
+        assert!(_res.is_err());
+
+        let info = mock_info("dao", &[]);
+        let _res = execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::AcceptOwnership {},
+        )
+        .unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetInfo {}).unwrap();
+        let value: InfoResponse = from_binary(&res).unwrap();
+        assert_eq!(Addr::unchecked("dao"), value.owner);
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::PendingOwner {}).unwrap();
+        let value: PendingOwnerResponse = from_binary(&res).unwrap();
+        assert_eq!(None, value.pending_owner);
+
+        // previous owner lost its rights
+        let info = mock_info("creator", &[]);
+        let _res = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::WithdrawAll {});
+        assert!(_res.is_err());
+    }
+
+    #[test]
+    fn ownership_proposal_expiry_and_cancel() {
+        let mut deps = mock_dependencies(&[]);
+
+        let msg = sale_msg();
+        let info = mock_info("creator", &[]);
+        let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let env = mock_env();
+        let expiry = Expiration::AtHeight(env.block.height + 10);
+
+        // cannot propose an already expired transfer
+        let info = mock_info("creator", &[]);
+        let msg = ExecuteMsg::ProposeNewOwner {
+            owner: "dao".to_string(),
+            expiry: Some(Expiration::AtHeight(env.block.height)),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info, msg);
+        assert!(_res.is_err());
+
+        let info = mock_info("creator", &[]);
+        let msg = ExecuteMsg::ProposeNewOwner {
+            owner: "dao".to_string(),
+            expiry: Some(expiry),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::PendingOwner {}).unwrap();
+        let value: PendingOwnerResponse = from_binary(&res).unwrap();
+        assert_eq!(Some(expiry), value.expiry);
+
+        // accepting after the expiry fails
+        let mut late_env = env.clone();
+        late_env.block.height += 10;
+        let info = mock_info("dao", &[]);
+        let res = execute(
+            deps.as_mut(),
+            late_env,
+            info,
+            ExecuteMsg::AcceptOwnership {},
+        );
+        match res {
+            Err(ContractError::OwnershipProposalExpired {}) => {}
+            _ => panic!("expected OwnershipProposalExpired error"),
+        }
+
+        // only the owner can cancel
+        let info = mock_info("dao", &[]);
+        let msg = ExecuteMsg::CancelOwnershipProposal {};
+        let _res = execute(deps.as_mut(), env.clone(), info, msg);
+        assert!(_res.is_err());
+
+        let info = mock_info("creator", &[]);
+        let msg = ExecuteMsg::CancelOwnershipProposal {};
+        let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        let info = mock_info("dao", &[]);
+        let _res = execute(deps.as_mut(), env, info, ExecuteMsg::AcceptOwnership {});
+        assert!(_res.is_err());
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetInfo {}).unwrap();
+        let value: InfoResponse = from_binary(&res).unwrap();
+        assert_eq!(Addr::unchecked("creator"), value.owner);
+    }
+
+    #[test]
+    fn migrate_v0_1_0_state() {
+        let mut deps = mock_dependencies(&[]);
+
+        // raw state written by a v0.1.0 deployment, without a contract version
+        deps.storage.set(
+            b"state",
+            br#"{"owner":"creator","cw20_address":"asdf","price":{"denom":"utoken","amount":"7"},"balance":"10"}"#,
+        );
+
+        let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+        assert_eq!(res.attributes[1], attr("from_version", "0.1.0"));
+        assert_eq!(res.attributes[2], attr("to_version", CONTRACT_VERSION));
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetInfo {}).unwrap();
+        let value: InfoResponse = from_binary(&res).unwrap();
+        assert_eq!(Addr::unchecked("creator"), value.owner);
+        assert_eq!(Addr::unchecked("asdf"), value.cw20_address);
+        assert_eq!(Uint128(10), value.balance);
+
+        let msg = QueryMsg::ListPrices {
+            start_after: None,
+            limit: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let value: PricesResponse = from_binary(&res).unwrap();
+        assert_eq!(coins(7, "utoken"), value.prices);
+
+        let version = cw2::get_contract_version(&deps.storage).unwrap();
+        assert_eq!(CONTRACT_NAME, version.contract);
+        assert_eq!(CONTRACT_VERSION, version.version);
+
+        // migrating again at the same version is a no-op
+        migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetInfo {}).unwrap();
+        let value: InfoResponse = from_binary(&res).unwrap();
+        assert_eq!(Uint128(10), value.balance);
+    }
+
+    #[test]
+    fn migrate_rejects_other_contract_and_downgrade() {
+        let mut deps = mock_dependencies(&[]);
+
+        set_contract_version(&mut deps.storage, "crates.io:cw20-base", "0.6.1").unwrap();
+        let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {});
+        match res {
+            Err(ContractError::CannotMigrate { previous_contract }) => {
+                assert_eq!("crates.io:cw20-base", previous_contract)
+            }
+            _ => panic!("expected CannotMigrate error"),
+        }
+
+        set_contract_version(&mut deps.storage, CONTRACT_NAME, "99.0.0").unwrap();
+        let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {});
+        match res {
+            Err(ContractError::CannotMigrateVersion { previous_version }) => {
+                assert_eq!("99.0.0", previous_version)
+            }
+            _ => panic!("expected CannotMigrateVersion error"),
+        }
+    }
+}
@@ -0,0 +1,194 @@
+use cosmwasm_std::{Addr, OverflowError, StdError, Uint128};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("{0}")]
+    Overflow(#[from] OverflowError),
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+    // Add any other custom errors you like here.
+    // Look at https://docs.rs/thiserror/1.0.21/thiserror/ for details.
+    #[error("Incorrect funds")]
+    IncorretFunds {},
+
+    #[error("Divide by zero error")]
+    DivideByZeroError {},
+
+    #[error("Invalid subtraction")]
+    SubtractionError {},
+
+    #[error("Unrecognized cw20 token: {address}")]
+    UnrecognizedToken { address: Addr },
+
+    #[error("No ownership proposal pending")]
+    NoPendingOwner {},
+
+    #[error("Ownership proposal expired")]
+    OwnershipProposalExpired {},
+
+    #[error("Cannot migrate from different contract type: {previous_contract}")]
+    CannotMigrate { previous_contract: String },
+
+    #[error("Cannot migrate from newer version: {previous_version}")]
+    CannotMigrateVersion { previous_version: String },
+
+    #[error("Invalid contract version: {version}")]
+    InvalidVersion { version: String },
+
+    #[error("Cannot pay with the cw20 being sold: {address}")]
+    InvalidPaymentToken { address: Addr },
+
+    #[error("Sale has not started")]
+    SaleNotStarted {},
+
+    #[error("Sale has ended")]
+    SaleEnded {},
+
+    #[error("Sale window can only be changed before the sale starts")]
+    SaleStarted {},
+
+    #[error("Sale must start before it ends")]
+    InvalidSaleWindow {},
+
+    #[error("Purchase is below the minimum of {min}")]
+    PurchaseBelowMinimum { min: Uint128 },
+
+    #[error("Purchase exceeds the address cap, {remaining} remaining")]
+    AddressCapExceeded { remaining: Uint128 },
+
+    #[error("Purchase exceeds the hard cap, {remaining} remaining")]
+    HardCapExceeded { remaining: Uint128 },
+
+    #[error("Purchase exceeds the allowlist allocation, {remaining} remaining")]
+    AllocationExceeded { remaining: Uint128 },
+
+    #[error("Sale is restricted to the allowlist")]
+    WhitelistOnly {},
+
+    #[error("Invalid Merkle proof")]
+    InvalidProof {},
+
+    #[error("Invalid Merkle root")]
+    InvalidMerkleRoot {},
+
+    #[error("Vesting must start before it ends, with the cliff in between")]
+    InvalidVestingSchedule {},
+
+    #[error("Nothing to claim")]
+    NothingToClaim {},
+
+    #[error("Price must be greater than zero")]
+    InvalidZeroPrice {},
+
+    #[error("No price set for denom: {denom}")]
+    PriceNotFound { denom: String },
+
+    #[error("No price provided for denom: {denom}")]
+    PriceNotProvided { denom: String },
+
+    #[error("Current price {price_current} is above the price provided {price_provided}")]
+    PriceAboveLimit {
+        price_current: Uint128,
+        price_provided: Uint128,
+    },
+
+    #[error("Invalid pricing mode")]
+    InvalidPricing {},
+
+    #[error("Batch auction needs a sale end, a reserve price and no pricing mode or allowlist")]
+    InvalidBatchAuction {},
+
+    #[error("Tokens are only sold by batch auction")]
+    BatchAuctionOnly {},
+
+    #[error("Sale is not a batch auction")]
+    NotBatchAuction {},
+
+    #[error("Bid is below the reserve price of {reserve_price}")]
+    BidBelowReserve { reserve_price: Uint128 },
+
+    #[error("Sale has not ended")]
+    SaleNotEnded {},
+
+    #[error("Auction is already finalized")]
+    AuctionFinalized {},
+
+    #[error("Auction is not finalized")]
+    AuctionNotFinalized {},
+
+    #[error("No bid to settle")]
+    NoBid {},
+
+    #[error("Fair launch needs a sale end, a price and no other sale mode or allowlist")]
+    InvalidFairLaunch {},
+
+    #[error("Tokens are only sold by fair launch deposit")]
+    FairLaunchOnly {},
+
+    #[error("Sale is not a fair launch")]
+    NotFairLaunch {},
+
+    #[error("Nothing to refund")]
+    NothingToRefund {},
+
+    #[error("Soft cap needs a sale end, no more than the hard cap and no other sale mode")]
+    InvalidSoftCap {},
+
+    #[error("Soft cap was not reached")]
+    SoftCapNotReached {},
+
+    #[error("Soft cap was reached")]
+    SoftCapReached {},
+
+    #[error("Proceeds split needs at least one recipient and no zero weights")]
+    InvalidProceedsSplit {},
+
+    #[error("Fee cannot be more than 10000 basis points")]
+    InvalidFee {},
+
+    #[error("Referral reward cannot be more than 10000 basis points or used with a soft cap")]
+    InvalidReferral {},
+
+    #[error("Cannot refer your own purchase")]
+    SelfReferral {},
+
+    #[error("Only {available} of the proceeds are available")]
+    InsufficientProceeds { available: Uint128 },
+
+    #[error("Only {available} tokens are available")]
+    InsufficientBalance { available: Uint128 },
+
+    #[error("Amount must be greater than zero")]
+    InvalidZeroAmount {},
+
+    #[error("Sale is paused")]
+    Paused {},
+
+    #[error("Cw20 payments are not accepted in this sale mode")]
+    Cw20PaymentDisabled {},
+
+    #[error("Receive message not accepted from this cw20")]
+    InvalidReceiveMsg {},
+
+    #[error("Sale has not been funded and priced")]
+    SaleUninitialized {},
+
+    #[error("Sale is already funded and priced")]
+    SaleInitialized {},
+
+    #[error("Denom and price must be set together")]
+    IncompletePrice {},
+
+    #[error("Price provided is not current")]
+    PriceNotCurrentError {
+        denom_current: String,
+        denom_provided: String,
+        price_current: Uint128,
+        price_provided: Uint128,
+    },
+}
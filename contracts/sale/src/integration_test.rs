@@ -0,0 +1,1143 @@
+#![cfg(test)]
+
+use cosmwasm_std::testing::{mock_env, MockApi, MockStorage};
+use cosmwasm_std::{
+    attr, coins, from_binary, to_binary, Addr, BalanceResponse, BankQuery, Coin, Decimal, Empty,
+    Uint128,
+};
+use cw0::Expiration;
+use cw20::{Cw20Coin, Cw20Contract, Cw20ExecuteMsg};
+use cw_multi_test::{App, Contract, ContractWrapper, SimpleBank};
+
+use crate::msg::{
+    ClearingResponse, CurrentPriceResponse, ExecuteMsg, FairLaunchResponse, InfoResponse,
+    InstantiateMsg, QueryMsg, ReceiveMsg, ReconcileResponse,
+};
+use crate::pricing::{Curve, Pricing};
+use crate::state::{BatchAuction, FairLaunch, Referral, ReferralReward, Status, VestingSchedule};
+
+fn mock_app() -> App {
+    let env = mock_env();
+    let api = Box::new(MockApi::default());
+    let bank = SimpleBank {};
+
+    App::new(api, env.block, bank, || Box::new(MockStorage::new()))
+}
+
+pub fn contract_sale() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        crate::contract::execute,
+        crate::contract::instantiate,
+        crate::contract::query,
+    );
+    Box::new(contract)
+}
+
+pub fn contract_cw20() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        cw20_base::contract::execute,
+        cw20_base::contract::instantiate,
+        cw20_base::contract::query,
+    );
+    Box::new(contract)
+}
+
+fn sale_msg(cw20_address: &Addr, denom: &str) -> InstantiateMsg {
+    InstantiateMsg {
+        cw20_address: cw20_address.clone(),
+        price: Some(Uint128(1)),
+        denom: Some(denom.to_string()),
+        start: None,
+        end: None,
+        max_per_address: None,
+        min_per_purchase: None,
+        hard_cap: None,
+        merkle_root: None,
+        vesting: None,
+        pricing: None,
+        batch_auction: None,
+        fair_launch: None,
+        soft_cap: None,
+        escrow_proceeds: false,
+        split: None,
+        fee: None,
+        referral: None,
+        live_balance: false,
+        guardian: None,
+    }
+}
+
+fn mock_cash(router: &mut App, owner: &Addr) -> Addr {
+    let cw20_id = router.store_code(contract_cw20());
+    let msg = cw20_base::msg::InstantiateMsg {
+        name: "Cash Money".to_string(),
+        symbol: "CASH".to_string(),
+        decimals: 2,
+        initial_balances: vec![Cw20Coin {
+            address: owner.to_string(),
+            amount: Uint128(5000),
+        }],
+        mint: None,
+    };
+    router
+        .instantiate_contract(cw20_id, owner.clone(), &msg, &[], "CASH")
+        .unwrap()
+}
+
+#[test]
+// receive cw20 tokens and release upon approval
+fn sale_happy_path() {
+    let mut router = mock_app();
+
+    const NATIVE_TOKEN_DENOM: &str = "token";
+
+    let owner = Addr::unchecked("owner");
+
+    // set up cw20 contract with some tokens
+    let cash_addr = mock_cash(&mut router, &owner);
+
+    // set up sale contract
+    let sale_id = router.store_code(contract_sale());
+    let price = Uint128::from(1u128);
+    let msg = InstantiateMsg {
+        price: Some(price),
+        ..sale_msg(&cash_addr, NATIVE_TOKEN_DENOM)
+    };
+    let sale_addr = router
+        .instantiate_contract(sale_id, owner.clone(), &msg, &[], "Sale")
+        .unwrap();
+
+    assert_ne!(cash_addr, sale_addr);
+
+    // set up cw20 helpers
+    let cash = Cw20Contract(cash_addr.clone());
+
+    // check initial balances
+    let owner_balance = cash.balance(&router, owner.clone()).unwrap();
+    assert_eq!(owner_balance, Uint128(5000));
+    let sale_balance = cash.balance(&router, sale_addr.clone()).unwrap();
+    assert_eq!(sale_balance, Uint128(0));
+
+    // send tokens to contract address
+    let send_msg = Cw20ExecuteMsg::Send {
+        contract: sale_addr.to_string(),
+        amount: Uint128::from(100u128),
+        msg: Some(to_binary(&ReceiveMsg::Receive {}).unwrap()),
+    };
+    let res = router
+        .execute_contract(owner.clone(), cash_addr.clone(), &send_msg, &[])
+        .unwrap();
+    println!("{:?}", res.attributes);
+    assert_eq!(4, res.attributes.len());
+
+    // ensure balances updated
+    let owner_balance = cash.balance(&router, owner.clone()).unwrap();
+    assert_eq!(owner_balance, Uint128(4900));
+    let sale_balance = cash.balance(&router, sale_addr.clone()).unwrap();
+    assert_eq!(sale_balance, Uint128(100));
+
+    // Create buyer address with some native tokens
+    let buyer = Addr::unchecked("buyer");
+    let funds = coins(2000, NATIVE_TOKEN_DENOM);
+    router.set_bank_balance(&buyer, funds).unwrap();
+
+    // Check cw20 token balance is zero
+    let buyer_balance = cash.balance(&router, buyer.clone()).unwrap();
+    assert_eq!(buyer_balance, Uint128(0));
+
+    // Buy cw20tokens through sale contract
+    let buy_msg = ExecuteMsg::Buy {
+        prices: coins(price.u128(), NATIVE_TOKEN_DENOM),
+        referrer: None,
+    };
+    let res = router
+        .execute_contract(
+            buyer.clone(),
+            sale_addr.clone(),
+            &buy_msg,
+            &[Coin {
+                amount: Uint128(10u128),
+                denom: NATIVE_TOKEN_DENOM.to_string(),
+            }],
+        )
+        .unwrap();
+    println!("{:?}", res.attributes);
+    assert_eq!(7, res.attributes.len());
+
+    let buyer_balance = cash.balance(&router, buyer.clone()).unwrap();
+    assert_eq!(buyer_balance, Uint128(10));
+
+    // Check balances of owner and buyer reflect the sale transaction
+    let query_res = router
+        .query(cosmwasm_std::QueryRequest::Bank(BankQuery::Balance {
+            address: buyer.to_string(),
+            denom: NATIVE_TOKEN_DENOM.to_string(),
+        }))
+        .unwrap();
+    let balance: BalanceResponse = from_binary(&query_res).unwrap();
+    assert_eq!(balance.amount.amount, Uint128(1990));
+
+    let query_res = router
+        .query(cosmwasm_std::QueryRequest::Bank(BankQuery::Balance {
+            address: owner.to_string(),
+            denom: NATIVE_TOKEN_DENOM.to_string(),
+        }))
+        .unwrap();
+    let balance: BalanceResponse = from_binary(&query_res).unwrap();
+    assert_eq!(balance.amount.amount, Uint128(10));
+
+    // Check cw20 token balance is 10
+    let buyer_balance = cash.balance(&router, buyer.clone()).unwrap();
+    assert_eq!(buyer_balance, Uint128(10));
+
+    let withdraw_msg = ExecuteMsg::WithdrawAll {};
+    let res = router
+        .execute_contract(owner.clone(), sale_addr.clone(), &withdraw_msg, &[])
+        .unwrap();
+    println!("{:?}", res.attributes);
+    assert_eq!(5, res.attributes.len());
+
+    // check cash has been returned to owner
+    let owner_balance = cash.balance(&router, owner.clone()).unwrap();
+    assert_eq!(owner_balance, Uint128(4990))
+}
+
+#[test]
+// a single Send funds and prices a sale instantiated without a price
+fn sale_create_with_send() {
+    let mut router = mock_app();
+
+    const NATIVE_TOKEN_DENOM: &str = "token";
+
+    let owner = Addr::unchecked("owner");
+
+    let cash_addr = mock_cash(&mut router, &owner);
+
+    let sale_id = router.store_code(contract_sale());
+    let msg = InstantiateMsg {
+        price: None,
+        denom: None,
+        ..sale_msg(&cash_addr, "token")
+    };
+    let sale_addr = router
+        .instantiate_contract(sale_id, owner.clone(), &msg, &[], "Sale")
+        .unwrap();
+
+    let buyer = Addr::unchecked("buyer");
+    router
+        .set_bank_balance(&buyer, coins(2000, NATIVE_TOKEN_DENOM))
+        .unwrap();
+    let buy_msg = ExecuteMsg::Buy {
+        prices: coins(2, NATIVE_TOKEN_DENOM),
+        referrer: None,
+    };
+    router
+        .execute_contract(
+            buyer.clone(),
+            sale_addr.clone(),
+            &buy_msg,
+            &coins(10, NATIVE_TOKEN_DENOM),
+        )
+        .unwrap_err();
+
+    let send_msg = Cw20ExecuteMsg::Send {
+        contract: sale_addr.to_string(),
+        amount: Uint128(100),
+        msg: Some(
+            to_binary(&ReceiveMsg::CreateSale {
+                denom: NATIVE_TOKEN_DENOM.to_string(),
+                price: Uint128(2),
+            })
+            .unwrap(),
+        ),
+    };
+    router
+        .execute_contract(owner.clone(), cash_addr.clone(), &send_msg, &[])
+        .unwrap();
+
+    let info: InfoResponse = router
+        .wrap()
+        .query_wasm_smart(&sale_addr, &QueryMsg::GetInfo {})
+        .unwrap();
+    assert_eq!(info.balance, Uint128(100));
+    assert_eq!(info.status, Status::Initialized);
+
+    router
+        .execute_contract(
+            buyer.clone(),
+            sale_addr.clone(),
+            &buy_msg,
+            &coins(10, NATIVE_TOKEN_DENOM),
+        )
+        .unwrap();
+    let cash = Cw20Contract(cash_addr);
+    assert_eq!(cash.balance(&router, buyer).unwrap(), Uint128(5));
+}
+
+#[test]
+// only the cw20 being sold can be deposited through the receive hook
+fn sale_rejects_foreign_cw20() {
+    let mut router = mock_app();
+
+    let owner = Addr::unchecked("owner");
+
+    // set up the cw20 being sold and an unrelated cw20
+    let cash_addr = mock_cash(&mut router, &owner);
+    let cw20_id = router.store_code(contract_cw20());
+    let msg = cw20_base::msg::InstantiateMsg {
+        name: "Fake Money".to_string(),
+        symbol: "FAKE".to_string(),
+        decimals: 2,
+        initial_balances: vec![Cw20Coin {
+            address: owner.to_string(),
+            amount: Uint128(5000),
+        }],
+        mint: None,
+    };
+    let fake_addr = router
+        .instantiate_contract(cw20_id, owner.clone(), &msg, &[], "FAKE")
+        .unwrap();
+
+    // set up sale contract
+    let sale_id = router.store_code(contract_sale());
+    let msg = sale_msg(&cash_addr, "token");
+    let sale_addr = router
+        .instantiate_contract(sale_id, owner.clone(), &msg, &[], "Sale")
+        .unwrap();
+
+    // sending the foreign token fails and leaves balances untouched
+    let send_msg = Cw20ExecuteMsg::Send {
+        contract: sale_addr.to_string(),
+        amount: Uint128::from(100u128),
+        msg: Some(to_binary(&ReceiveMsg::Receive {}).unwrap()),
+    };
+    let res = router.execute_contract(owner.clone(), fake_addr.clone(), &send_msg, &[]);
+    assert!(res.is_err());
+
+    let fake = Cw20Contract(fake_addr);
+    let owner_balance = fake.balance(&router, owner).unwrap();
+    assert_eq!(owner_balance, Uint128(5000));
+    let sale_balance = fake.balance(&router, sale_addr.clone()).unwrap();
+    assert_eq!(sale_balance, Uint128(0));
+
+    let info: InfoResponse = router
+        .wrap()
+        .query_wasm_smart(sale_addr, &QueryMsg::GetInfo {})
+        .unwrap();
+    assert_eq!(info.balance, Uint128(0));
+}
+
+#[test]
+// pay for the sale with a second cw20 sent through the receive hook
+fn sale_with_cw20_payment() {
+    let mut router = mock_app();
+
+    let owner = Addr::unchecked("owner");
+    let buyer = Addr::unchecked("buyer");
+
+    // set up the cw20 being sold and a stablecoin cw20 held by the buyer
+    let cash_addr = mock_cash(&mut router, &owner);
+    let cw20_id = router.store_code(contract_cw20());
+    let msg = cw20_base::msg::InstantiateMsg {
+        name: "Stable Money".to_string(),
+        symbol: "STABLE".to_string(),
+        decimals: 2,
+        initial_balances: vec![Cw20Coin {
+            address: buyer.to_string(),
+            amount: Uint128(1000),
+        }],
+        mint: None,
+    };
+    let stable_addr = router
+        .instantiate_contract(cw20_id, owner.clone(), &msg, &[], "STABLE")
+        .unwrap();
+
+    // set up sale contract accepting the stablecoin
+    let sale_id = router.store_code(contract_sale());
+    let msg = sale_msg(&cash_addr, "token");
+    let sale_addr = router
+        .instantiate_contract(sale_id, owner.clone(), &msg, &[], "Sale")
+        .unwrap();
+
+    let msg = ExecuteMsg::SetCw20Price {
+        address: stable_addr.to_string(),
+        price: Uint128(4),
+    };
+    router
+        .execute_contract(owner.clone(), sale_addr.clone(), &msg, &[])
+        .unwrap();
+
+    // fund the sale
+    let send_msg = Cw20ExecuteMsg::Send {
+        contract: sale_addr.to_string(),
+        amount: Uint128::from(100u128),
+        msg: Some(to_binary(&ReceiveMsg::Receive {}).unwrap()),
+    };
+    router
+        .execute_contract(owner.clone(), cash_addr.clone(), &send_msg, &[])
+        .unwrap();
+
+    // buy with 42 stable at a price of 4
+    let send_msg = Cw20ExecuteMsg::Send {
+        contract: sale_addr.to_string(),
+        amount: Uint128::from(42u128),
+        msg: Some(to_binary(&ReceiveMsg::Buy { price: Uint128(4) }).unwrap()),
+    };
+    router
+        .execute_contract(buyer.clone(), stable_addr.clone(), &send_msg, &[])
+        .unwrap();
+
+    let cash = Cw20Contract(cash_addr);
+    let stable = Cw20Contract(stable_addr);
+    assert_eq!(cash.balance(&router, buyer.clone()).unwrap(), Uint128(10));
+    assert_eq!(
+        cash.balance(&router, sale_addr.clone()).unwrap(),
+        Uint128(90)
+    );
+    assert_eq!(stable.balance(&router, owner).unwrap(), Uint128(40));
+    assert_eq!(stable.balance(&router, buyer).unwrap(), Uint128(960));
+    assert_eq!(
+        stable.balance(&router, sale_addr.clone()).unwrap(),
+        Uint128(0)
+    );
+
+    let info: InfoResponse = router
+        .wrap()
+        .query_wasm_smart(sale_addr, &QueryMsg::GetInfo {})
+        .unwrap();
+    assert_eq!(info.balance, Uint128(90));
+}
+
+#[test]
+// the price decays with block time from the start price down to the floor
+fn sale_dutch_auction() {
+    let mut router = mock_app();
+
+    let owner = Addr::unchecked("owner");
+    let buyer = Addr::unchecked("buyer");
+
+    let cash_addr = mock_cash(&mut router, &owner);
+
+    // price falls from 1000 to 100 over 900 seconds
+    let start_time = router.block_info().time;
+    let sale_id = router.store_code(contract_sale());
+    let msg = InstantiateMsg {
+        pricing: Some(Pricing::DutchAuction {
+            denom: "token".to_string(),
+            start_price: Uint128(1000),
+            floor_price: Uint128(100),
+            start_time,
+            end_time: start_time.plus_seconds(900),
+        }),
+        ..sale_msg(&cash_addr, "token")
+    };
+    let sale_addr = router
+        .instantiate_contract(sale_id, owner.clone(), &msg, &[], "Sale")
+        .unwrap();
+
+    let send_msg = Cw20ExecuteMsg::Send {
+        contract: sale_addr.to_string(),
+        amount: Uint128::from(100u128),
+        msg: Some(to_binary(&ReceiveMsg::Receive {}).unwrap()),
+    };
+    router
+        .execute_contract(owner.clone(), cash_addr.clone(), &send_msg, &[])
+        .unwrap();
+
+    let curve = [
+        (0, 1000),
+        (90, 910),
+        (450, 550),
+        (899, 101),
+        (900, 100),
+        (5000, 100),
+    ];
+    for (elapsed, expected) in curve.iter() {
+        router.update_block(|b| b.time = start_time.plus_seconds(*elapsed));
+        let res: CurrentPriceResponse = router
+            .wrap()
+            .query_wasm_smart(sale_addr.clone(), &QueryMsg::CurrentPrice {})
+            .unwrap();
+        assert_eq!(res.price, Coin::new(*expected, "token"));
+    }
+
+    // halfway through the price is 550, a stale price of 500 is rejected
+    router.update_block(|b| b.time = start_time.plus_seconds(450));
+    router
+        .set_bank_balance(&buyer, coins(2000, "token"))
+        .unwrap();
+    let buy_msg = ExecuteMsg::Buy {
+        prices: coins(500, "token"),
+        referrer: None,
+    };
+    let res = router.execute_contract(
+        buyer.clone(),
+        sale_addr.clone(),
+        &buy_msg,
+        &coins(2000, "token"),
+    );
+    assert!(res.is_err());
+
+    // 2000 buys 3 tokens at 550, the rest is refunded
+    let buy_msg = ExecuteMsg::Buy {
+        prices: coins(600, "token"),
+        referrer: None,
+    };
+    router
+        .execute_contract(
+            buyer.clone(),
+            sale_addr.clone(),
+            &buy_msg,
+            &coins(2000, "token"),
+        )
+        .unwrap();
+
+    let cash = Cw20Contract(cash_addr);
+    assert_eq!(cash.balance(&router, buyer.clone()).unwrap(), Uint128(3));
+    let balance: Coin = router.wrap().query_balance(&buyer, "token").unwrap();
+    assert_eq!(balance.amount, Uint128(350));
+    let balance: Coin = router.wrap().query_balance(&owner, "token").unwrap();
+    assert_eq!(balance.amount, Uint128(1650));
+}
+
+#[test]
+// every purchase moves the price up the curve for the next buyer
+fn sale_bonding_curve() {
+    let mut router = mock_app();
+
+    let owner = Addr::unchecked("owner");
+    let first = Addr::unchecked("first");
+    let second = Addr::unchecked("second");
+
+    let cash_addr = mock_cash(&mut router, &owner);
+
+    // the first x tokens cost x + x^2 / 2
+    let sale_id = router.store_code(contract_sale());
+    let msg = InstantiateMsg {
+        pricing: Some(Pricing::BondingCurve {
+            denom: "token".to_string(),
+            curve: Curve::Linear {
+                base_price: Uint128(1),
+                slope: Decimal::one(),
+            },
+        }),
+        ..sale_msg(&cash_addr, "token")
+    };
+    let sale_addr = router
+        .instantiate_contract(sale_id, owner.clone(), &msg, &[], "Sale")
+        .unwrap();
+
+    let send_msg = Cw20ExecuteMsg::Send {
+        contract: sale_addr.to_string(),
+        amount: Uint128::from(100u128),
+        msg: Some(to_binary(&ReceiveMsg::Receive {}).unwrap()),
+    };
+    router
+        .execute_contract(owner.clone(), cash_addr.clone(), &send_msg, &[])
+        .unwrap();
+
+    router
+        .set_bank_balance(&first, coins(100, "token"))
+        .unwrap();
+    router
+        .set_bank_balance(&second, coins(100, "token"))
+        .unwrap();
+
+    // 100 buys the first 13 tokens for 97
+    let buy_msg = ExecuteMsg::Buy {
+        prices: coins(10, "token"),
+        referrer: None,
+    };
+    router
+        .execute_contract(
+            first.clone(),
+            sale_addr.clone(),
+            &buy_msg,
+            &coins(100, "token"),
+        )
+        .unwrap();
+
+    let res: CurrentPriceResponse = router
+        .wrap()
+        .query_wasm_smart(sale_addr.clone(), &QueryMsg::CurrentPrice {})
+        .unwrap();
+    assert_eq!(res.price, Coin::new(15, "token"));
+
+    // the same 100 only buys 5 more, at an average above 16
+    let buy_msg = ExecuteMsg::Buy {
+        prices: coins(17, "token"),
+        referrer: None,
+    };
+    router
+        .execute_contract(
+            second.clone(),
+            sale_addr.clone(),
+            &buy_msg,
+            &coins(100, "token"),
+        )
+        .unwrap();
+
+    let cash = Cw20Contract(cash_addr);
+    assert_eq!(cash.balance(&router, first.clone()).unwrap(), Uint128(13));
+    assert_eq!(cash.balance(&router, second.clone()).unwrap(), Uint128(5));
+    let balance: Coin = router.wrap().query_balance(&first, "token").unwrap();
+    assert_eq!(balance.amount, Uint128(3));
+    let balance: Coin = router.wrap().query_balance(&second, "token").unwrap();
+    assert_eq!(balance.amount, Uint128(17));
+    let balance: Coin = router.wrap().query_balance(&owner, "token").unwrap();
+    assert_eq!(balance.amount, Uint128(180));
+
+    let info: InfoResponse = router
+        .wrap()
+        .query_wasm_smart(sale_addr, &QueryMsg::GetInfo {})
+        .unwrap();
+    assert_eq!(info.tokens_sold, Uint128(18));
+}
+
+#[test]
+// bids are filled from the highest price down and all pay the clearing price
+fn sale_batch_auction() {
+    let mut router = mock_app();
+
+    let owner = Addr::unchecked("owner");
+    let bidders: Vec<Addr> = ["bidder_a", "bidder_b", "bidder_c", "bidder_d"]
+        .iter()
+        .map(|name| Addr::unchecked(*name))
+        .collect();
+
+    let cash_addr = mock_cash(&mut router, &owner);
+
+    let end = router.block_info().time.plus_seconds(100);
+    let sale_id = router.store_code(contract_sale());
+    let msg = InstantiateMsg {
+        end: Some(Expiration::AtTime(end)),
+        batch_auction: Some(BatchAuction {
+            denom: "token".to_string(),
+            reserve_price: Uint128(1),
+        }),
+        ..sale_msg(&cash_addr, "token")
+    };
+    let sale_addr = router
+        .instantiate_contract(sale_id, owner.clone(), &msg, &[], "Sale")
+        .unwrap();
+
+    // 100 tokens go to auction
+    let send_msg = Cw20ExecuteMsg::Send {
+        contract: sale_addr.to_string(),
+        amount: Uint128::from(100u128),
+        msg: Some(to_binary(&ReceiveMsg::Receive {}).unwrap()),
+    };
+    router
+        .execute_contract(owner.clone(), cash_addr.clone(), &send_msg, &[])
+        .unwrap();
+
+    // 60 at 5, then 90 at 4 for the 40 left, while the bid at 2 misses out
+    let bids = [(60u128, 5u128), (50, 4), (40, 4), (30, 2)];
+    for (bidder, (amount, max_price)) in bidders.iter().zip(bids.iter()) {
+        let escrow = coins(amount * max_price, "token");
+        router.set_bank_balance(bidder, escrow.clone()).unwrap();
+        let msg = ExecuteMsg::Bid {
+            amount: Uint128(*amount),
+            max_price: Uint128(*max_price),
+        };
+        router
+            .execute_contract(bidder.clone(), sale_addr.clone(), &msg, &escrow)
+            .unwrap();
+    }
+
+    // bids cannot be finalized before the sale ends
+    let msg = ExecuteMsg::Finalize { limit: Some(1) };
+    let res = router.execute_contract(owner.clone(), sale_addr.clone(), &msg, &[]);
+    assert!(res.is_err());
+
+    // one price level per call
+    router.update_block(|b| b.time = end.plus_seconds(1));
+    router
+        .execute_contract(owner.clone(), sale_addr.clone(), &msg, &[])
+        .unwrap();
+    let res: ClearingResponse = router
+        .wrap()
+        .query_wasm_smart(sale_addr.clone(), &QueryMsg::Clearing {})
+        .unwrap();
+    assert_eq!(res.price, None);
+
+    router
+        .execute_contract(owner.clone(), sale_addr.clone(), &msg, &[])
+        .unwrap();
+    let res: ClearingResponse = router
+        .wrap()
+        .query_wasm_smart(sale_addr.clone(), &QueryMsg::Clearing {})
+        .unwrap();
+    assert_eq!(res.price, Some(Uint128(4)));
+    assert_eq!(res.sold, Uint128(100));
+
+    // the owner is paid for every token sold at the clearing price
+    let balance: Coin = router.wrap().query_balance(&owner, "token").unwrap();
+    assert_eq!(balance.amount, Uint128(400));
+
+    let msg = ExecuteMsg::SettleBid {};
+    for bidder in bidders.iter() {
+        router
+            .execute_contract(bidder.clone(), sale_addr.clone(), &msg, &[])
+            .unwrap();
+    }
+
+    // the 40 left at 4 are shared 22 / 18 between the bids of 50 and 40
+    let cash = Cw20Contract(cash_addr);
+    let expected = [(60, 60), (22, 112), (18, 88), (0, 60)];
+    for (bidder, (tokens, refund)) in bidders.iter().zip(expected.iter()) {
+        assert_eq!(
+            cash.balance(&router, bidder.clone()).unwrap(),
+            Uint128(*tokens)
+        );
+        let balance: Coin = router.wrap().query_balance(bidder, "token").unwrap();
+        assert_eq!(balance.amount, Uint128(*refund));
+    }
+    assert_eq!(
+        router
+            .wrap()
+            .query_balance(&sale_addr, "token")
+            .unwrap()
+            .amount,
+        Uint128(0)
+    );
+
+    // settled bids cannot be settled again
+    let res = router.execute_contract(bidders[0].clone(), sale_addr, &msg, &[]);
+    assert!(res.is_err());
+}
+
+#[test]
+fn sale_fair_launch() {
+    let mut router = mock_app();
+
+    let owner = Addr::unchecked("owner");
+    let depositors: Vec<Addr> = ["depositor_a", "depositor_b", "depositor_c"]
+        .iter()
+        .map(|name| Addr::unchecked(*name))
+        .collect();
+
+    let cash_addr = mock_cash(&mut router, &owner);
+
+    let end = router.block_info().time.plus_seconds(100);
+    let sale_id = router.store_code(contract_sale());
+    let msg = InstantiateMsg {
+        end: Some(Expiration::AtTime(end)),
+        fair_launch: Some(FairLaunch {
+            denom: "token".to_string(),
+            price: Uint128(3),
+        }),
+        ..sale_msg(&cash_addr, "token")
+    };
+    let sale_addr = router
+        .instantiate_contract(sale_id, owner.clone(), &msg, &[], "Sale")
+        .unwrap();
+
+    let send_msg = Cw20ExecuteMsg::Send {
+        contract: sale_addr.to_string(),
+        amount: Uint128::from(100u128),
+        msg: Some(to_binary(&ReceiveMsg::Receive {}).unwrap()),
+    };
+    router
+        .execute_contract(owner.clone(), cash_addr.clone(), &send_msg, &[])
+        .unwrap();
+
+    // 181 deposited only covers 60 of the 100 tokens at 3
+    let deposits = [100u128, 50, 31];
+    for (depositor, amount) in depositors.iter().zip(deposits.iter()) {
+        let funds = coins(*amount, "token");
+        router.set_bank_balance(depositor, funds.clone()).unwrap();
+        router
+            .execute_contract(
+                depositor.clone(),
+                sale_addr.clone(),
+                &ExecuteMsg::Deposit {},
+                &funds,
+            )
+            .unwrap();
+    }
+
+    let res = router.execute_contract(
+        owner.clone(),
+        sale_addr.clone(),
+        &ExecuteMsg::WithdrawAll {},
+        &[],
+    );
+    assert!(res.is_err());
+
+    router.update_block(|b| b.time = end.plus_seconds(1));
+    for depositor in depositors.iter() {
+        router
+            .execute_contract(
+                depositor.clone(),
+                sale_addr.clone(),
+                &ExecuteMsg::Claim {},
+                &[],
+            )
+            .unwrap();
+        router
+            .execute_contract(
+                depositor.clone(),
+                sale_addr.clone(),
+                &ExecuteMsg::Refund {},
+                &[],
+            )
+            .unwrap();
+    }
+
+    let res: FairLaunchResponse = router
+        .wrap()
+        .query_wasm_smart(sale_addr.clone(), &QueryMsg::FairLaunch {})
+        .unwrap();
+    assert_eq!(res.sold, Some(Uint128(60)));
+    assert_eq!(res.raised, Uint128(180));
+
+    // the 1 left over from 181 goes back with the first refund
+    let cash = Cw20Contract(cash_addr);
+    let expected = [(33, 1), (16, 0), (11, 0)];
+    for (depositor, (tokens, refund)) in depositors.iter().zip(expected.iter()) {
+        assert_eq!(
+            cash.balance(&router, depositor.clone()).unwrap(),
+            Uint128(*tokens)
+        );
+        let balance: Coin = router.wrap().query_balance(depositor, "token").unwrap();
+        assert_eq!(balance.amount, Uint128(*refund));
+    }
+    let balance: Coin = router.wrap().query_balance(&owner, "token").unwrap();
+    assert_eq!(balance.amount, Uint128(180));
+
+    // the unsold 40 go back to the owner
+    router
+        .execute_contract(
+            owner.clone(),
+            sale_addr.clone(),
+            &ExecuteMsg::WithdrawAll {},
+            &[],
+        )
+        .unwrap();
+    assert_eq!(cash.balance(&router, owner).unwrap(), Uint128(4940));
+    assert_eq!(
+        router
+            .wrap()
+            .query_balance(&sale_addr, "token")
+            .unwrap()
+            .amount,
+        Uint128(0)
+    );
+}
+
+#[test]
+fn sale_soft_cap_reached() {
+    let mut router = mock_app();
+
+    let owner = Addr::unchecked("owner");
+    let buyers = [Addr::unchecked("buyer_a"), Addr::unchecked("buyer_b")];
+
+    let cash_addr = mock_cash(&mut router, &owner);
+
+    let end = router.block_info().time.plus_seconds(100);
+    let sale_id = router.store_code(contract_sale());
+    let msg = InstantiateMsg {
+        price: Some(Uint128::from(2u128)),
+        end: Some(Expiration::AtTime(end)),
+        soft_cap: Some(Uint128(50)),
+        ..sale_msg(&cash_addr, "token")
+    };
+    let sale_addr = router
+        .instantiate_contract(sale_id, owner.clone(), &msg, &[], "Sale")
+        .unwrap();
+
+    let send_msg = Cw20ExecuteMsg::Send {
+        contract: sale_addr.to_string(),
+        amount: Uint128::from(100u128),
+        msg: Some(to_binary(&ReceiveMsg::Receive {}).unwrap()),
+    };
+    router
+        .execute_contract(owner.clone(), cash_addr.clone(), &send_msg, &[])
+        .unwrap();
+
+    // 55 tokens sold against a soft cap of 50
+    for (buyer, amount) in buyers.iter().zip([60u128, 50].iter()) {
+        let funds = coins(*amount, "token");
+        router.set_bank_balance(buyer, funds.clone()).unwrap();
+        let msg = ExecuteMsg::Buy {
+            prices: coins(2, "token"),
+            referrer: None,
+        };
+        router
+            .execute_contract(buyer.clone(), sale_addr.clone(), &msg, &funds)
+            .unwrap();
+    }
+
+    // nothing leaves the contract before the sale ends
+    let cash = Cw20Contract(cash_addr);
+    assert_eq!(
+        cash.balance(&router, buyers[0].clone()).unwrap(),
+        Uint128(0)
+    );
+    let res = router.execute_contract(
+        buyers[0].clone(),
+        sale_addr.clone(),
+        &ExecuteMsg::Claim {},
+        &[],
+    );
+    assert!(res.is_err());
+
+    router.update_block(|b| b.time = end.plus_seconds(1));
+    for buyer in buyers.iter() {
+        router
+            .execute_contract(buyer.clone(), sale_addr.clone(), &ExecuteMsg::Claim {}, &[])
+            .unwrap();
+    }
+    assert_eq!(
+        cash.balance(&router, buyers[0].clone()).unwrap(),
+        Uint128(30)
+    );
+    assert_eq!(
+        cash.balance(&router, buyers[1].clone()).unwrap(),
+        Uint128(25)
+    );
+
+    // a successful sale cannot be refunded
+    let res = router.execute_contract(
+        buyers[0].clone(),
+        sale_addr.clone(),
+        &ExecuteMsg::Refund {},
+        &[],
+    );
+    assert!(res.is_err());
+
+    // the owner takes the proceeds and the 45 unsold tokens
+    router
+        .execute_contract(
+            owner.clone(),
+            sale_addr.clone(),
+            &ExecuteMsg::WithdrawAll {},
+            &[],
+        )
+        .unwrap();
+    let balance: Coin = router.wrap().query_balance(&owner, "token").unwrap();
+    assert_eq!(balance.amount, Uint128(110));
+    assert_eq!(cash.balance(&router, owner).unwrap(), Uint128(4945));
+    assert_eq!(
+        router
+            .wrap()
+            .query_balance(&sale_addr, "token")
+            .unwrap()
+            .amount,
+        Uint128(0)
+    );
+}
+
+#[test]
+fn sale_referral_bonus_tokens() {
+    let mut router = mock_app();
+
+    let owner = Addr::unchecked("owner");
+    let buyer = Addr::unchecked("buyer");
+    let referrer = Addr::unchecked("referrer");
+
+    let cash_addr = mock_cash(&mut router, &owner);
+
+    let sale_id = router.store_code(contract_sale());
+    let msg = InstantiateMsg {
+        price: Some(Uint128::from(2u128)),
+        referral: Some(Referral {
+            bps: 500,
+            reward: ReferralReward::Tokens,
+        }),
+        ..sale_msg(&cash_addr, "token")
+    };
+    let sale_addr = router
+        .instantiate_contract(sale_id, owner.clone(), &msg, &[], "Sale")
+        .unwrap();
+
+    let send_msg = Cw20ExecuteMsg::Send {
+        contract: sale_addr.to_string(),
+        amount: Uint128::from(100u128),
+        msg: Some(to_binary(&ReceiveMsg::Receive {}).unwrap()),
+    };
+    router
+        .execute_contract(owner.clone(), cash_addr.clone(), &send_msg, &[])
+        .unwrap();
+
+    // 40 buys 20 tokens, the referrer gets a 5% bonus on top
+    let funds = coins(40, "token");
+    router.set_bank_balance(&buyer, funds.clone()).unwrap();
+    let msg = ExecuteMsg::Buy {
+        prices: coins(2, "token"),
+        referrer: Some(referrer.to_string()),
+    };
+    router
+        .execute_contract(buyer.clone(), sale_addr.clone(), &msg, &funds)
+        .unwrap();
+
+    let cash = Cw20Contract(cash_addr);
+    assert_eq!(cash.balance(&router, buyer).unwrap(), Uint128(20));
+    assert_eq!(cash.balance(&router, referrer).unwrap(), Uint128(1));
+    let balance: Coin = router.wrap().query_balance(&owner, "token").unwrap();
+    assert_eq!(balance.amount, Uint128(40));
+
+    let res: InfoResponse = router
+        .wrap()
+        .query_wasm_smart(sale_addr, &QueryMsg::GetInfo {})
+        .unwrap();
+    // the bonus counts as sold
+    assert_eq!(res.balance, Uint128(79));
+    assert_eq!(res.tokens_sold, Uint128(21));
+}
+
+#[test]
+// tokens transferred without the receive hook are picked up by Sync, vesting tokens are not
+fn sale_sync_balance() {
+    let mut router = mock_app();
+
+    const NATIVE_TOKEN_DENOM: &str = "token";
+
+    let owner = Addr::unchecked("owner");
+    let cash_addr = mock_cash(&mut router, &owner);
+
+    let now = mock_env().block.time;
+    let sale_id = router.store_code(contract_sale());
+    let mut msg = sale_msg(&cash_addr, NATIVE_TOKEN_DENOM);
+    msg.vesting = Some(VestingSchedule {
+        start: now,
+        cliff: now.plus_seconds(100),
+        end: now.plus_seconds(1000),
+    });
+    let sale_addr = router
+        .instantiate_contract(sale_id, owner.clone(), &msg, &[], "Sale")
+        .unwrap();
+
+    let send_msg = Cw20ExecuteMsg::Send {
+        contract: sale_addr.to_string(),
+        amount: Uint128(100),
+        msg: Some(to_binary(&ReceiveMsg::Receive {}).unwrap()),
+    };
+    router
+        .execute_contract(owner.clone(), cash_addr.clone(), &send_msg, &[])
+        .unwrap();
+
+    // the tokens bought stay locked in the sale
+    let buyer = Addr::unchecked("buyer");
+    router
+        .set_bank_balance(&buyer, coins(10, NATIVE_TOKEN_DENOM))
+        .unwrap();
+    let buy_msg = ExecuteMsg::Buy {
+        prices: coins(1, NATIVE_TOKEN_DENOM),
+        referrer: None,
+    };
+    router
+        .execute_contract(
+            buyer.clone(),
+            sale_addr.clone(),
+            &buy_msg,
+            &coins(10, NATIVE_TOKEN_DENOM),
+        )
+        .unwrap();
+
+    // a plain transfer is not seen by the sale
+    let transfer_msg = Cw20ExecuteMsg::Transfer {
+        recipient: sale_addr.to_string(),
+        amount: Uint128(50),
+    };
+    router
+        .execute_contract(owner.clone(), cash_addr.clone(), &transfer_msg, &[])
+        .unwrap();
+
+    let res: ReconcileResponse = router
+        .wrap()
+        .query_wasm_smart(&sale_addr, &QueryMsg::Reconcile {})
+        .unwrap();
+    assert_eq!(
+        res,
+        ReconcileResponse {
+            balance: Uint128(90),
+            owed: Uint128(10),
+            cw20_balance: Uint128(150),
+            surplus: Uint128(50),
+            deficit: Uint128(0),
+        }
+    );
+
+    // only the owner can sync
+    router
+        .execute_contract(buyer, sale_addr.clone(), &ExecuteMsg::Sync {}, &[])
+        .unwrap_err();
+    router
+        .execute_contract(owner, sale_addr.clone(), &ExecuteMsg::Sync {}, &[])
+        .unwrap();
+
+    let res: ReconcileResponse = router
+        .wrap()
+        .query_wasm_smart(&sale_addr, &QueryMsg::Reconcile {})
+        .unwrap();
+    assert_eq!(res.balance, Uint128(140));
+    assert_eq!(res.surplus, Uint128(0));
+}
+
+#[test]
+// a live balance sale sells and returns tokens that were transferred without the receive hook
+fn sale_live_balance() {
+    let mut router = mock_app();
+
+    const NATIVE_TOKEN_DENOM: &str = "token";
+
+    let owner = Addr::unchecked("owner");
+    let cash_addr = mock_cash(&mut router, &owner);
+
+    let sale_id = router.store_code(contract_sale());
+    let mut msg = sale_msg(&cash_addr, NATIVE_TOKEN_DENOM);
+    msg.live_balance = true;
+    let sale_addr = router
+        .instantiate_contract(sale_id, owner.clone(), &msg, &[], "Sale")
+        .unwrap();
+
+    let transfer_msg = Cw20ExecuteMsg::Transfer {
+        recipient: sale_addr.to_string(),
+        amount: Uint128(100),
+    };
+    router
+        .execute_contract(owner.clone(), cash_addr.clone(), &transfer_msg, &[])
+        .unwrap();
+
+    let buyer = Addr::unchecked("buyer");
+    router
+        .set_bank_balance(&buyer, coins(10, NATIVE_TOKEN_DENOM))
+        .unwrap();
+    let buy_msg = ExecuteMsg::Buy {
+        prices: coins(1, NATIVE_TOKEN_DENOM),
+        referrer: None,
+    };
+    router
+        .execute_contract(
+            buyer.clone(),
+            sale_addr.clone(),
+            &buy_msg,
+            &coins(10, NATIVE_TOKEN_DENOM),
+        )
+        .unwrap();
+    let cash = Cw20Contract(cash_addr.clone());
+    assert_eq!(cash.balance(&router, buyer).unwrap(), Uint128(10));
+
+    let transfer_msg = Cw20ExecuteMsg::Transfer {
+        recipient: sale_addr.to_string(),
+        amount: Uint128(20),
+    };
+    router
+        .execute_contract(owner.clone(), cash_addr, &transfer_msg, &[])
+        .unwrap();
+
+    // everything left in the sale goes back to the owner
+    let res = router
+        .execute_contract(
+            owner.clone(),
+            sale_addr.clone(),
+            &ExecuteMsg::WithdrawAll {},
+            &[],
+        )
+        .unwrap();
+    assert_eq!(res.attributes[0], attr("amount", Uint128(110)));
+    assert_eq!(cash.balance(&router, sale_addr).unwrap(), Uint128(0));
+    assert_eq!(cash.balance(&router, owner).unwrap(), Uint128(4990));
+}
@@ -0,0 +1,8 @@
+pub mod contract;
+pub mod error;
+mod integration_test;
+pub mod merkle;
+pub mod migrations;
+pub mod msg;
+pub mod pricing;
+pub mod state;
@@ -0,0 +1,151 @@
+use sha2::{Digest, Sha256};
+
+use cosmwasm_std::{Addr, Uint128};
+
+use crate::error::ContractError;
+
+/// Allocation claimed by a buyer, with the proof that it is in the allowlist.
+pub struct Allocation {
+    pub amount: Uint128,
+    pub proof: Vec<String>,
+}
+
+/// Leaf of the allowlist tree: `sha256("{address}:{allocation}")`. The
+/// delimiter keeps `buyer1` with 1100 apart from `buyer11` with 100.
+pub fn leaf_hash(address: &Addr, allocation: Uint128) -> [u8; 32] {
+    sha256(format!("{}:{}", address, allocation).as_bytes())
+}
+
+/// Checks a hex encoded Merkle root is 32 bytes long.
+pub fn validate_root(root: &str) -> Result<(), ContractError> {
+    decode_hash(root)
+        .map(|_| ())
+        .map_err(|_| ContractError::InvalidMerkleRoot {})
+}
+
+/// Folds the proof into the leaf, hashing each pair in sorted order, and
+/// compares the result with the root.
+pub fn verify_proof(root: &str, leaf: [u8; 32], proof: &[String]) -> Result<bool, ContractError> {
+    let root = decode_hash(root)?;
+    let mut hash = leaf;
+    for sibling in proof {
+        hash = hash_pair(&hash, &decode_hash(sibling)?);
+    }
+    Ok(hash == root)
+}
+
+fn decode_hash(hex_hash: &str) -> Result<[u8; 32], ContractError> {
+    let mut hash = [0u8; 32];
+    hex::decode_to_slice(hex_hash, &mut hash).map_err(|_| ContractError::InvalidProof {})?;
+    Ok(hash)
+}
+
+fn hash_pair(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (first, second) = if a <= b { (a, b) } else { (b, a) };
+    let mut hasher = Sha256::new();
+    hasher.update(first);
+    hasher.update(second);
+    hasher.finalize().into()
+}
+
+fn sha256(data: &[u8]) -> [u8; 32] {
+    Sha256::digest(data).into()
+}
+
+/// Builds a tree over `leaves`, returning the hex root and the hex proof of
+/// every leaf. A node without a sibling is promoted to the next level as is.
+#[cfg(test)]
+pub fn build_tree(leaves: &[[u8; 32]]) -> (String, Vec<Vec<String>>) {
+    let mut proofs: Vec<Vec<String>> = vec![vec![]; leaves.len()];
+    // position of every leaf in the current level
+    let mut positions: Vec<usize> = (0..leaves.len()).collect();
+    let mut level: Vec<[u8; 32]> = leaves.to_vec();
+
+    while level.len() > 1 {
+        for (leaf, position) in positions.iter_mut().enumerate() {
+            let sibling = *position ^ 1;
+            if sibling < level.len() {
+                proofs[leaf].push(hex::encode(level[sibling]));
+            }
+            *position /= 2;
+        }
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [a, b] => hash_pair(a, b),
+                [a] => *a,
+                _ => unreachable!(),
+            })
+            .collect();
+    }
+
+    (hex::encode(level[0]), proofs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaves(count: usize) -> Vec<(Addr, Uint128)> {
+        (0..count)
+            .map(|i| {
+                (
+                    Addr::unchecked(format!("buyer{}", i)),
+                    Uint128((i as u128 + 1) * 100),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn leaf_hash_vector() {
+        // sha256("buyer0:100")
+        assert_eq!(
+            "548434e519e134a241edd4a3b13c6b0f89149c568bf125928d082f1efb0a2df4",
+            hex::encode(leaf_hash(&Addr::unchecked("buyer0"), Uint128(100)))
+        );
+    }
+
+    #[test]
+    fn leaf_hash_separates_fields() {
+        assert_ne!(
+            leaf_hash(&Addr::unchecked("buyer1"), Uint128(1100)),
+            leaf_hash(&Addr::unchecked("buyer11"), Uint128(100))
+        );
+    }
+
+    #[test]
+    fn verify_every_leaf_of_large_tree() {
+        for count in &[1usize, 2, 3, 255, 300] {
+            let entries = leaves(*count);
+            let hashes: Vec<[u8; 32]> = entries
+                .iter()
+                .map(|(address, allocation)| leaf_hash(address, *allocation))
+                .collect();
+            let (root, proofs) = build_tree(&hashes);
+
+            for (i, (address, allocation)) in entries.iter().enumerate() {
+                let leaf = leaf_hash(address, *allocation);
+                assert!(verify_proof(&root, leaf, &proofs[i]).unwrap());
+
+                // a different allocation does not verify
+                let leaf = leaf_hash(address, allocation.checked_add(Uint128(1)).unwrap());
+                assert!(!verify_proof(&root, leaf, &proofs[i]).unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn reject_malformed_hashes() {
+        let leaf = leaf_hash(&Addr::unchecked("buyer0"), Uint128(100));
+        assert!(validate_root("zz").is_err());
+        assert!(validate_root(&hex::encode([0u8; 31])).is_err());
+        assert!(validate_root(&hex::encode([0u8; 32])).is_ok());
+
+        let root = hex::encode([0u8; 32]);
+        match verify_proof(&root, leaf, &["abc".to_string()]) {
+            Err(ContractError::InvalidProof {}) => {}
+            _ => panic!("expected InvalidProof error"),
+        }
+    }
+}
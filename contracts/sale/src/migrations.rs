@@ -0,0 +1,76 @@
+use cosmwasm_std::{Storage, Uint128};
+
+use crate::error::ContractError;
+use crate::state::{State, Status, PRICES, STATE};
+
+/// Storage layout of the contract as deployed at v0.1.0, before contract
+/// versions were tracked.
+pub mod v0_1_0 {
+    use schemars::JsonSchema;
+    use serde::{Deserialize, Serialize};
+
+    use cosmwasm_std::{Addr, Coin, Uint128};
+    use cw_storage_plus::Item;
+
+    pub const VERSION: &str = "0.1.0";
+
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+    pub struct State {
+        pub owner: Addr,
+        pub cw20_address: Addr,
+        pub price: Coin,
+        pub balance: Uint128,
+    }
+
+    pub const STATE: Item<State> = Item::new("state");
+}
+
+/// Rewrites the v0.1.0 `State` into the current layout.
+pub fn migrate_from_v0_1_0(storage: &mut dyn Storage) -> Result<(), ContractError> {
+    let old = v0_1_0::STATE.load(storage)?;
+    let state = State {
+        owner: old.owner,
+        cw20_address: old.cw20_address,
+        balance: old.balance,
+        start: None,
+        end: None,
+        max_per_address: None,
+        min_per_purchase: None,
+        hard_cap: None,
+        tokens_sold: Uint128(0),
+        merkle_root: None,
+        vesting: None,
+        pricing: None,
+        batch_auction: None,
+        fair_launch: None,
+        soft_cap: None,
+        escrow_proceeds: false,
+        split: None,
+        fee: None,
+        referral: None,
+        status: Status::Initialized,
+        live_balance: false,
+        paused: false,
+        guardian: None,
+    };
+    STATE.save(storage, &state)?;
+    PRICES.save(storage, &old.price.denom, &old.price.amount)?;
+    Ok(())
+}
+
+/// Parses a `major.minor.patch` version so stored versions can be ordered.
+pub fn parse_version(version: &str) -> Result<(u64, u64, u64), ContractError> {
+    let parts: Vec<u64> = version
+        .split('.')
+        .map(|part| part.parse::<u64>())
+        .collect::<Result<_, _>>()
+        .map_err(|_| ContractError::InvalidVersion {
+            version: version.to_string(),
+        })?;
+    match parts.as_slice() {
+        [major, minor, patch] => Ok((*major, *minor, *patch)),
+        _ => Err(ContractError::InvalidVersion {
+            version: version.to_string(),
+        }),
+    }
+}
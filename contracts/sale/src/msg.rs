@@ -0,0 +1,343 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{Addr, Binary, Coin, Uint128};
+
+use cw0::Expiration;
+use cw20::{Cw20Coin, Cw20ReceiveMsg};
+
+use crate::pricing::Pricing;
+use crate::state::{
+    BatchAuction, Bid, FairLaunch, Fee, Phase, ProceedsSplit, Referral, Status, VestingSchedule,
+};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InstantiateMsg {
+    pub cw20_address: Addr,
+    // denom and price can be left out and set by the CreateSale deposit instead
+    pub denom: Option<String>,
+    pub price: Option<Uint128>,
+    pub start: Option<Expiration>,
+    pub end: Option<Expiration>,
+    pub max_per_address: Option<Uint128>,
+    pub min_per_purchase: Option<Uint128>,
+    pub hard_cap: Option<Uint128>,
+    // merkle_root cannot be combined with batch_auction or fair_launch
+    pub merkle_root: Option<String>,
+    pub vesting: Option<VestingSchedule>,
+    // payment cw20s are refused while pricing or soft_cap is set
+    pub pricing: Option<Pricing>,
+    pub batch_auction: Option<BatchAuction>,
+    pub fair_launch: Option<FairLaunch>,
+    pub soft_cap: Option<Uint128>,
+    // escrow_proceeds keeps native and payment cw20 proceeds until WithdrawProceeds
+    #[serde(default)]
+    pub escrow_proceeds: bool,
+    pub split: Option<ProceedsSplit>,
+    pub fee: Option<Fee>,
+    pub referral: Option<Referral>,
+    #[serde(default)]
+    pub live_balance: bool,
+    pub guardian: Option<Addr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecuteMsg {
+    SetPrice {
+        denom: String,
+        price: Uint128,
+    },
+    RemovePrice {
+        denom: String,
+    },
+    SetCw20Price {
+        address: String,
+        price: Uint128,
+    },
+    RemoveCw20Price {
+        address: String,
+    },
+    // prices are the current prices of the denoms sent, guarding against price changes.
+    // Under a pricing mode the price is the most the buyer pays per token. The referrer, if
+    // any, is credited with the purchase
+    Buy {
+        prices: Vec<Coin>,
+        referrer: Option<String>,
+    },
+    // WhitelistBuy proves the sender's allocation during the allowlist phase
+    WhitelistBuy {
+        prices: Vec<Coin>,
+        allocation: Uint128,
+        proof: Vec<String>,
+    },
+    SetMerkleRoot {
+        merkle_root: Option<String>,
+    },
+    // SetProceedsSplit shares future proceeds between weighted recipients, or pays the owner if unset
+    SetProceedsSplit {
+        split: Option<ProceedsSplit>,
+    },
+    WithdrawAll {},
+    // Withdraw sends amount of the unsold tokens to the recipient or the owner, owner only.
+    // With msg the tokens are sent to the recipient contract through Cw20 Send
+    Withdraw {
+        amount: Uint128,
+        recipient: Option<String>,
+        msg: Option<Binary>,
+    },
+    // Sync sets the balance to the live cw20 balance less the tokens owed to buyers, owner only
+    Sync {},
+    // SetFee changes the platform fee of future purchases and optionally hands over the
+    // collector, collector only
+    SetFee {
+        bps: u16,
+        collector: Option<String>,
+    },
+    // ReleaseFees pays the fees set aside under a soft cap to the collector once the sale
+    // ends with the soft cap met, anyone can call it
+    ReleaseFees {},
+    // WithdrawProceeds sends escrowed proceeds in denom, a native denom or a payment cw20
+    // address, all of them by default, to the recipient or the owner and split recipients
+    WithdrawProceeds {
+        denom: String,
+        amount: Option<Uint128>,
+        recipient: Option<String>,
+    },
+    // Claim releases the sender's vested tokens, their tokens once a soft cap is met, or their
+    // share of a fair launch once it ends
+    Claim {},
+    // Deposit adds the sent funds to the sender's fair launch deposit
+    Deposit {},
+    // Refund returns the sender's payments when a soft cap is missed, or the part of their
+    // fair launch deposit not spent on tokens
+    Refund {},
+    // Bid escrows amount * max_price during a batch auction, replacing any earlier bid
+    Bid {
+        amount: Uint128,
+        max_price: Uint128,
+    },
+    // Finalize walks up to limit price levels towards the clearing price once the auction ends
+    Finalize {
+        limit: Option<u32>,
+    },
+    // SettleBid releases the sender's tokens and refund once the auction is finalized
+    SettleBid {},
+    Receive(Cw20ReceiveMsg),
+    ProposeNewOwner {
+        owner: String,
+        expiry: Option<Expiration>,
+    },
+    AcceptOwnership {},
+    CancelOwnershipProposal {},
+    SetSaleWindow {
+        start: Option<Expiration>,
+        end: Option<Expiration>,
+    },
+    // Pause stops buying, bids, deposits and the receive hook, owner or guardian
+    Pause {},
+    // Unpause resumes a paused sale, owner only
+    Unpause {},
+    // SetGuardian changes or removes the address allowed to pause, owner only
+    SetGuardian {
+        guardian: Option<String>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateMsg {}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReceiveMsg {
+    Receive {},
+    // CreateSale deposits the sale token and sets its price in one Send, owner only
+    CreateSale {
+        denom: String,
+        price: Uint128,
+    },
+    // Buy pays with the sent cw20, price guards against price changes
+    Buy {
+        price: Uint128,
+    },
+    WhitelistBuy {
+        price: Uint128,
+        allocation: Uint128,
+        proof: Vec<String>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    // GetCount returns the current count as a json-encoded number
+    GetInfo {},
+    // PendingOwner returns the proposed owner, if any
+    PendingOwner {},
+    // Allowance returns how many tokens an address can still buy
+    Allowance {
+        address: String,
+    },
+    // VestingInfo returns the tokens bought, claimed and claimable by an address
+    VestingInfo {
+        address: String,
+    },
+    // CurrentPrice returns the price of a token under the pricing mode
+    CurrentPrice {},
+    // Tiers returns the price and remaining supply of each tier of a tiered sale
+    Tiers {},
+    // Clearing returns the progress and result of the batch auction finalization
+    Clearing {},
+    // Bid returns the open bid of an address
+    Bid {
+        address: String,
+    },
+    // ReferralStats returns the purchases referred by an address
+    ReferralStats {
+        address: String,
+    },
+    // Proceeds returns the native and payment cw20 proceeds held by the contract
+    Proceeds {},
+    // FairLaunch returns the fair launch totals
+    FairLaunch {},
+    // Deposit returns the fair launch deposit of an address
+    Deposit {
+        address: String,
+    },
+    // Reconcile compares the balance with the live cw20 balance of the contract
+    Reconcile {},
+    // ListPrices returns the price of every accepted denom
+    ListPrices {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    // ListCw20Prices returns the price of every accepted payment cw20
+    ListCw20Prices {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InfoResponse {
+    pub owner: Addr,
+    pub cw20_address: Addr,
+    pub balance: Uint128,
+    pub start: Option<Expiration>,
+    pub end: Option<Expiration>,
+    pub phase: Phase,
+    pub max_per_address: Option<Uint128>,
+    pub min_per_purchase: Option<Uint128>,
+    pub hard_cap: Option<Uint128>,
+    pub tokens_sold: Uint128,
+    pub merkle_root: Option<String>,
+    pub vesting: Option<VestingSchedule>,
+    pub pricing: Option<Pricing>,
+    pub batch_auction: Option<BatchAuction>,
+    pub fair_launch: Option<FairLaunch>,
+    pub soft_cap: Option<Uint128>,
+    pub escrow_proceeds: bool,
+    pub split: Option<ProceedsSplit>,
+    pub fee: Option<Fee>,
+    pub referral: Option<Referral>,
+    pub status: Status,
+    pub live_balance: bool,
+    pub paused: bool,
+    pub guardian: Option<Addr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingOwnerResponse {
+    pub pending_owner: Option<Addr>,
+    pub expiry: Option<Expiration>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PricesResponse {
+    pub prices: Vec<Coin>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Cw20PricesResponse {
+    pub prices: Vec<Cw20Coin>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AllowanceResponse {
+    pub purchased: Uint128,
+    pub remaining: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VestingInfoResponse {
+    pub total: Uint128,
+    pub claimed: Uint128,
+    pub claimable: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CurrentPriceResponse {
+    pub price: Coin,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TierInfo {
+    pub supply: Uint128,
+    pub price: Uint128,
+    pub remaining: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TiersResponse {
+    pub tiers: Vec<TierInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ClearingResponse {
+    pub supply: Uint128,
+    pub price: Option<Uint128>,
+    pub sold: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BidResponse {
+    pub bid: Option<Bid>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FairLaunchResponse {
+    pub deposits: Uint128,
+    pub sold: Option<Uint128>,
+    pub raised: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DepositResponse {
+    pub deposit: Uint128,
+    pub claimed: bool,
+    pub refunded: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ProceedsResponse {
+    pub proceeds: Vec<Coin>,
+    pub cw20_proceeds: Vec<Cw20Coin>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ReferralStatsResponse {
+    pub referrals: u64,
+    pub volume: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ReconcileResponse {
+    pub balance: Uint128,
+    pub owed: Uint128,
+    pub cw20_balance: Uint128,
+    // tokens held beyond the balance and owed tokens, adopted by Sync
+    pub surplus: Uint128,
+    // tokens missing from the balance and owed tokens
+    pub deficit: Uint128,
+}
@@ -0,0 +1,611 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{
+    Decimal, Fraction, OverflowError, OverflowOperation, StdError, StdResult, Timestamp, Uint128,
+};
+
+use crate::error::ContractError;
+
+/// Dynamic pricing of the cw20 in a single native denom. When a sale has no
+/// pricing mode the fixed per-denom price list is used instead.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Pricing {
+    /// Price decays linearly from `start_price` to `floor_price` between
+    /// `start_time` and `end_time`, and stays at the floor afterwards.
+    DutchAuction {
+        denom: String,
+        start_price: Uint128,
+        floor_price: Uint128,
+        start_time: Timestamp,
+        end_time: Timestamp,
+    },
+    /// Price follows `curve` as tokens are sold, a purchase costs the area
+    /// under the curve between `tokens_sold` and `tokens_sold + amount`.
+    BondingCurve { denom: String, curve: Curve },
+    /// Tokens are sold through `tiers` in order, each at its own price. A
+    /// purchase crossing a boundary pays each tier's price for its part.
+    Tiered {
+        denom: String,
+        tiers: Vec<PriceTier>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PriceTier {
+    pub supply: Uint128,
+    pub price: Uint128,
+}
+
+/// Price of a token once `supply` tokens have been sold.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Curve {
+    /// `price`
+    Constant { price: Uint128 },
+    /// `base_price + slope * supply`
+    Linear { base_price: Uint128, slope: Decimal },
+    /// `base_price * (1 + growth) ^ supply`, priced token by token so the
+    /// area is the sum of the geometric series.
+    Exponential {
+        base_price: Uint128,
+        growth: Decimal,
+    },
+    /// `base_price + coefficient * sqrt(supply)`
+    SquareRoot {
+        base_price: Uint128,
+        coefficient: Decimal,
+    },
+}
+
+const DECIMAL_FRACTIONAL: u128 = 1_000_000_000_000_000_000;
+
+impl Pricing {
+    pub fn denom(&self) -> &str {
+        match self {
+            Pricing::DutchAuction { denom, .. } => denom,
+            Pricing::BondingCurve { denom, .. } => denom,
+            Pricing::Tiered { denom, .. } => denom,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), ContractError> {
+        match self {
+            Pricing::DutchAuction {
+                start_price,
+                floor_price,
+                start_time,
+                end_time,
+                ..
+            } => {
+                if *floor_price == Uint128(0) {
+                    return Err(ContractError::InvalidZeroPrice {});
+                }
+                if start_price < floor_price || start_time >= end_time {
+                    return Err(ContractError::InvalidPricing {});
+                }
+            }
+            Pricing::BondingCurve { curve, .. } => curve.validate()?,
+            Pricing::Tiered { tiers, .. } => {
+                if tiers.is_empty() || tiers.iter().any(|tier| tier.supply == Uint128(0)) {
+                    return Err(ContractError::InvalidPricing {});
+                }
+                if tiers.iter().any(|tier| tier.price == Uint128(0)) {
+                    return Err(ContractError::InvalidZeroPrice {});
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Price of the next token at `now`, once `tokens_sold` have been sold.
+    pub fn current_price(&self, now: Timestamp, tokens_sold: Uint128) -> StdResult<Uint128> {
+        Ok(match self {
+            Pricing::DutchAuction {
+                start_price,
+                floor_price,
+                start_time,
+                end_time,
+                ..
+            } => {
+                if now <= *start_time {
+                    *start_price
+                } else if now >= *end_time {
+                    *floor_price
+                } else {
+                    // start_price >= floor_price is checked on validation
+                    let range = Uint128(start_price.u128() - floor_price.u128());
+                    let decay = range.multiply_ratio(
+                        now.nanos() - start_time.nanos(),
+                        end_time.nanos() - start_time.nanos(),
+                    );
+                    Uint128(start_price.u128() - decay.u128())
+                }
+            }
+            Pricing::BondingCurve { curve, .. } => Uint128(curve.cost(tokens_sold.u128(), 1)?),
+            Pricing::Tiered { tiers, .. } => {
+                let mut end = Uint128(0);
+                let tier = tiers.iter().find(|tier| {
+                    end += tier.supply;
+                    tokens_sold < end
+                });
+                match tier {
+                    Some(tier) => tier.price,
+                    None => return Err(StdError::generic_err("All tiers are sold out")),
+                }
+            }
+        })
+    }
+
+    /// Number of tokens `funds` buys at `now` once `tokens_sold` have been
+    /// sold, and what they cost.
+    pub fn buy(
+        &self,
+        funds: Uint128,
+        now: Timestamp,
+        tokens_sold: Uint128,
+    ) -> StdResult<(Uint128, Uint128)> {
+        match self {
+            Pricing::DutchAuction { .. } => {
+                // the floor price is never zero
+                let price = self.current_price(now, tokens_sold)?;
+                let amount = funds.multiply_ratio(1u128, price);
+                Ok((amount, amount.checked_mul(price)?))
+            }
+            Pricing::BondingCurve { curve, .. } => {
+                let (amount, cost) = curve.buy(funds.u128(), tokens_sold.u128())?;
+                Ok((Uint128(amount), Uint128(cost)))
+            }
+            Pricing::Tiered { tiers, .. } => {
+                let mut amount = Uint128(0);
+                let mut cost = Uint128(0);
+                let mut end = Uint128(0);
+                for tier in tiers {
+                    end = end.checked_add(tier.supply)?;
+                    let sold = tokens_sold.checked_add(amount)?;
+                    if sold >= end {
+                        continue;
+                    }
+                    // fill what is left of this tier, or as much as the funds allow
+                    let available = end.checked_sub(sold)?;
+                    let affordable = funds.checked_sub(cost)?.multiply_ratio(1u128, tier.price);
+                    let bought = available.min(affordable);
+                    amount = amount.checked_add(bought)?;
+                    cost = cost.checked_add(bought.checked_mul(tier.price)?)?;
+                    if bought < available {
+                        break;
+                    }
+                }
+                Ok((amount, cost))
+            }
+        }
+    }
+
+    /// Remaining supply of each tier once `tokens_sold` have been sold, if
+    /// the sale is tiered.
+    pub fn tiers_remaining(&self, tokens_sold: Uint128) -> Option<Vec<(PriceTier, Uint128)>> {
+        let tiers = match self {
+            Pricing::Tiered { tiers, .. } => tiers,
+            _ => return None,
+        };
+        let mut start = Uint128(0);
+        Some(
+            tiers
+                .iter()
+                .map(|tier| {
+                    let sold = tokens_sold.saturating_sub(start).min(tier.supply);
+                    start = start.saturating_add(tier.supply);
+                    (tier.clone(), tier.supply.saturating_sub(sold))
+                })
+                .collect(),
+        )
+    }
+}
+
+impl Curve {
+    fn validate(&self) -> Result<(), ContractError> {
+        let base_price = match self {
+            Curve::Constant { price } => *price,
+            Curve::Linear { base_price, .. } => *base_price,
+            Curve::Exponential { base_price, growth } => {
+                if growth.is_zero() {
+                    return Err(ContractError::InvalidPricing {});
+                }
+                *base_price
+            }
+            Curve::SquareRoot { base_price, .. } => *base_price,
+        };
+        // a zero base price would round the first tokens down to free
+        if base_price == Uint128(0) {
+            return Err(ContractError::InvalidZeroPrice {});
+        }
+        Ok(())
+    }
+
+    /// Cost of the first `supply` tokens, the area under the curve from zero.
+    /// Rounding down here rather than per purchase means splitting a purchase
+    /// never makes it cheaper.
+    fn supply_cost(&self, supply: u128) -> Result<u128, OverflowError> {
+        match self {
+            Curve::Constant { price } => checked_mul(price.u128(), supply),
+            Curve::Linear { base_price, slope } => {
+                let area = mul_div(
+                    mul_div(supply, supply, 2)?,
+                    slope.numerator(),
+                    DECIMAL_FRACTIONAL,
+                )?;
+                checked_add(checked_mul(base_price.u128(), supply)?, area)
+            }
+            Curve::Exponential { base_price, growth } => {
+                let factor = pow_decimal(DECIMAL_FRACTIONAL + growth.numerator(), supply)?;
+                mul_div(
+                    base_price.u128(),
+                    factor - DECIMAL_FRACTIONAL,
+                    growth.numerator(),
+                )
+            }
+            Curve::SquareRoot {
+                base_price,
+                coefficient,
+            } => {
+                // supply ^ 3/2, with the square root taken to six decimals
+                let root = isqrt(checked_mul(supply, 1_000_000_000_000)?);
+                let power = mul_div(supply, root, 1_000_000)?;
+                let area = mul_div(
+                    mul_div(power, coefficient.numerator(), DECIMAL_FRACTIONAL)?,
+                    2,
+                    3,
+                )?;
+                checked_add(checked_mul(base_price.u128(), supply)?, area)
+            }
+        }
+    }
+
+    fn cost(&self, tokens_sold: u128, amount: u128) -> Result<u128, OverflowError> {
+        let end = checked_add(tokens_sold, amount)?;
+        // the cost of a supply only grows with it, this cannot underflow
+        Ok(self.supply_cost(end)? - self.supply_cost(tokens_sold)?)
+    }
+
+    /// Most tokens `funds` buys after `tokens_sold`, found by doubling an
+    /// upper bound then bisecting, with what they cost.
+    fn buy(&self, funds: u128, tokens_sold: u128) -> Result<(u128, u128), OverflowError> {
+        let affordable = |amount: u128| match self.cost(tokens_sold, amount) {
+            Ok(cost) => cost <= funds,
+            Err(_) => false,
+        };
+
+        let mut low = 0u128;
+        let mut high = 1u128;
+        while affordable(high) {
+            low = high;
+            high = checked_mul(high, 2)?;
+        }
+        while high - low > 1 {
+            let mid = low + (high - low) / 2;
+            if affordable(mid) {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+
+        Ok((low, self.cost(tokens_sold, low)?))
+    }
+}
+
+fn checked_add(a: u128, b: u128) -> Result<u128, OverflowError> {
+    a.checked_add(b)
+        .ok_or_else(|| OverflowError::new(OverflowOperation::Add, a, b))
+}
+
+fn checked_mul(a: u128, b: u128) -> Result<u128, OverflowError> {
+    a.checked_mul(b)
+        .ok_or_else(|| OverflowError::new(OverflowOperation::Mul, a, b))
+}
+
+/// `a * b / c` rounded down, through a 256 bit product as cosmwasm-std has
+/// no `Uint256` yet. `c` must not be zero.
+pub(crate) fn mul_div(a: u128, b: u128, c: u128) -> Result<u128, OverflowError> {
+    const MASK: u128 = u64::MAX as u128;
+    let (a1, a0) = (a >> 64, a & MASK);
+    let (b1, b0) = (b >> 64, b & MASK);
+    let (p00, p01, p10, p11) = (a0 * b0, a0 * b1, a1 * b0, a1 * b1);
+    let mid = (p00 >> 64) + (p01 & MASK) + (p10 & MASK);
+    let low = (p00 & MASK) | (mid << 64);
+    let high = p11 + (p01 >> 64) + (p10 >> 64) + (mid >> 64);
+
+    // the quotient only fits in 128 bits while the high half is below c
+    if high >= c {
+        return Err(OverflowError::new(OverflowOperation::Mul, a, b));
+    }
+    let mut remainder = high;
+    let mut quotient = 0u128;
+    for i in (0..128).rev() {
+        let carry = remainder >> 127;
+        remainder = (remainder << 1) | ((low >> i) & 1);
+        quotient <<= 1;
+        if carry == 1 || remainder >= c {
+            remainder = remainder.wrapping_sub(c);
+            quotient |= 1;
+        }
+    }
+    Ok(quotient)
+}
+
+/// `base ^ exp` for a `base` with 18 decimals, by squaring.
+fn pow_decimal(mut base: u128, mut exp: u128) -> Result<u128, OverflowError> {
+    let mut result = DECIMAL_FRACTIONAL;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = mul_div(result, base, DECIMAL_FRACTIONAL)?;
+        }
+        exp >>= 1;
+        if exp > 0 {
+            base = mul_div(base, base, DECIMAL_FRACTIONAL)?;
+        }
+    }
+    Ok(result)
+}
+
+/// Square root rounded down, by Newton's method.
+fn isqrt(n: u128) -> u128 {
+    if n < 2 {
+        return n;
+    }
+    let bits = 128 - n.leading_zeros();
+    let mut x = 1u128 << ((bits + 1) / 2);
+    loop {
+        let y = (x + n / x) / 2;
+        if y >= x {
+            return x;
+        }
+        x = y;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn dutch_auction() -> Pricing {
+        Pricing::DutchAuction {
+            denom: "ujuno".to_string(),
+            start_price: Uint128(1000),
+            floor_price: Uint128(100),
+            start_time: Timestamp::from_seconds(1000),
+            end_time: Timestamp::from_seconds(1900),
+        }
+    }
+
+    fn price_at(pricing: &Pricing, seconds: u64) -> Uint128 {
+        pricing
+            .current_price(Timestamp::from_seconds(seconds), Uint128(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn dutch_auction_price_curve() {
+        let pricing = dutch_auction();
+        assert_eq!(Uint128(1000), price_at(&pricing, 0));
+        assert_eq!(Uint128(1000), price_at(&pricing, 1000));
+        assert_eq!(Uint128(900), price_at(&pricing, 1100));
+        assert_eq!(Uint128(550), price_at(&pricing, 1450));
+        assert_eq!(Uint128(101), price_at(&pricing, 1899));
+        assert_eq!(Uint128(100), price_at(&pricing, 1900));
+        assert_eq!(Uint128(100), price_at(&pricing, 5000));
+
+        // 2000 buys 3 tokens at 550, 350 is left over
+        let (amount, cost) = pricing
+            .buy(Uint128(2000), Timestamp::from_seconds(1450), Uint128(0))
+            .unwrap();
+        assert_eq!(Uint128(3), amount);
+        assert_eq!(Uint128(1650), cost);
+    }
+
+    #[test]
+    fn dutch_auction_validation() {
+        assert!(dutch_auction().validate().is_ok());
+
+        let pricing = Pricing::DutchAuction {
+            denom: "ujuno".to_string(),
+            start_price: Uint128(100),
+            floor_price: Uint128(1000),
+            start_time: Timestamp::from_seconds(1000),
+            end_time: Timestamp::from_seconds(1900),
+        };
+        assert!(pricing.validate().is_err());
+
+        let pricing = Pricing::DutchAuction {
+            denom: "ujuno".to_string(),
+            start_price: Uint128(1000),
+            floor_price: Uint128(0),
+            start_time: Timestamp::from_seconds(1000),
+            end_time: Timestamp::from_seconds(1900),
+        };
+        assert!(pricing.validate().is_err());
+
+        let pricing = Pricing::DutchAuction {
+            denom: "ujuno".to_string(),
+            start_price: Uint128(1000),
+            floor_price: Uint128(100),
+            start_time: Timestamp::from_seconds(1900),
+            end_time: Timestamp::from_seconds(1900),
+        };
+        assert!(pricing.validate().is_err());
+    }
+
+    #[test]
+    fn math_helpers() {
+        assert_eq!(6, mul_div(3, 4, 2).unwrap());
+        // the product overflows 128 bits but the quotient does not
+        assert_eq!(
+            u128::MAX / 3,
+            mul_div(u128::MAX, u128::MAX / 3, u128::MAX).unwrap()
+        );
+        assert_eq!(1 << 100, mul_div(1 << 100, 1 << 100, 1 << 100).unwrap());
+        assert!(mul_div(u128::MAX, 2, 1).is_err());
+
+        assert_eq!(0, isqrt(0));
+        assert_eq!(1, isqrt(3));
+        assert_eq!(2, isqrt(4));
+        assert_eq!(1_000_000_000, isqrt(1_000_000_000_000_000_000));
+        assert_eq!(u64::MAX as u128, isqrt(u128::MAX));
+
+        let two = 2 * DECIMAL_FRACTIONAL;
+        assert_eq!(1024 * DECIMAL_FRACTIONAL, pow_decimal(two, 10).unwrap());
+        assert!(pow_decimal(two, 200).is_err());
+    }
+
+    #[test]
+    fn curve_costs() {
+        let constant = Curve::Constant { price: Uint128(5) };
+        assert_eq!(50, constant.cost(1000, 10).unwrap());
+
+        // 1 + x/2 between 10 and 20 is 10 + (400 - 100) / 4
+        let linear = Curve::Linear {
+            base_price: Uint128(1),
+            slope: Decimal::percent(50),
+        };
+        assert_eq!(85, linear.cost(10, 10).unwrap());
+
+        // 2 ^ x summed over the first 10 tokens
+        let exponential = Curve::Exponential {
+            base_price: Uint128(1),
+            growth: Decimal::one(),
+        };
+        assert_eq!(1023, exponential.cost(0, 10).unwrap());
+        assert_eq!(1024, exponential.cost(10, 1).unwrap());
+
+        // 3 * sqrt(x) between 0 and 100 is 2 * 100 ^ 3/2
+        let square_root = Curve::SquareRoot {
+            base_price: Uint128(0),
+            coefficient: Decimal::from_str("3").unwrap(),
+        };
+        assert_eq!(2000, square_root.cost(0, 100).unwrap());
+        assert_eq!(14000, square_root.cost(100, 300).unwrap());
+    }
+
+    #[test]
+    fn curve_buys_most_tokens_for_funds() {
+        let linear = Curve::Linear {
+            base_price: Uint128(1),
+            slope: Decimal::percent(50),
+        };
+        // 85 buys tokens 10 to 20 exactly, 100 buys one more at 11
+        assert_eq!((10, 85), linear.buy(85, 10).unwrap());
+        assert_eq!((11, 96), linear.buy(100, 10).unwrap());
+        assert_eq!((0, 0), linear.buy(5, 10).unwrap());
+
+        // a split purchase costs the same as a single one
+        let (first, first_cost) = linear.buy(40, 10).unwrap();
+        let (second, second_cost) = linear.buy(45, 10 + first).unwrap();
+        assert_eq!((5, 36), (first, first_cost));
+        assert_eq!((4, 38), (second, second_cost));
+        assert_eq!(74, linear.cost(10, 9).unwrap());
+
+        // funds beyond what the curve can price are left over
+        let exponential = Curve::Exponential {
+            base_price: Uint128(1),
+            growth: Decimal::one(),
+        };
+        assert_eq!((10, 1023), exponential.buy(1500, 0).unwrap());
+        assert_eq!((68, (1 << 68) - 1), exponential.buy(u128::MAX, 0).unwrap());
+    }
+
+    #[test]
+    fn tiers_split_purchases_across_boundaries() {
+        let pricing = Pricing::Tiered {
+            denom: "ujuno".to_string(),
+            tiers: vec![
+                PriceTier {
+                    supply: Uint128(10),
+                    price: Uint128(2),
+                },
+                PriceTier {
+                    supply: Uint128(5),
+                    price: Uint128(3),
+                },
+                PriceTier {
+                    supply: Uint128(5),
+                    price: Uint128(10),
+                },
+            ],
+        };
+        let now = Timestamp::from_seconds(0);
+        assert!(pricing.validate().is_ok());
+
+        // 8 left at 2 then 4 at 3, with 2 of the funds left over
+        assert_eq!(
+            (Uint128(12), Uint128(28)),
+            pricing.buy(Uint128(30), now, Uint128(2)).unwrap()
+        );
+        assert_eq!(Uint128(3), pricing.current_price(now, Uint128(10)).unwrap());
+
+        // the last tier caps the sale
+        assert_eq!(
+            (Uint128(6), Uint128(53)),
+            pricing.buy(Uint128(1000), now, Uint128(14)).unwrap()
+        );
+        assert!(pricing.current_price(now, Uint128(20)).is_err());
+
+        let remaining: Vec<Uint128> = pricing
+            .tiers_remaining(Uint128(12))
+            .unwrap()
+            .into_iter()
+            .map(|(_, remaining)| remaining)
+            .collect();
+        assert_eq!(vec![Uint128(0), Uint128(3), Uint128(5)], remaining);
+
+        let pricing = Pricing::Tiered {
+            denom: "ujuno".to_string(),
+            tiers: vec![],
+        };
+        assert!(pricing.validate().is_err());
+    }
+
+    #[test]
+    fn curve_validation() {
+        let pricing = |curve: Curve| Pricing::BondingCurve {
+            denom: "ujuno".to_string(),
+            curve,
+        };
+        assert!(pricing(Curve::Constant { price: Uint128(0) })
+            .validate()
+            .is_err());
+        assert!(pricing(Curve::Linear {
+            base_price: Uint128(0),
+            slope: Decimal::zero(),
+        })
+        .validate()
+        .is_err());
+        assert!(matches!(
+            pricing(Curve::Linear {
+                base_price: Uint128(0),
+                slope: Decimal::percent(1),
+            })
+            .validate(),
+            Err(ContractError::InvalidZeroPrice {})
+        ));
+        assert!(pricing(Curve::Linear {
+            base_price: Uint128(1),
+            slope: Decimal::percent(1),
+        })
+        .validate()
+        .is_ok());
+        assert!(pricing(Curve::SquareRoot {
+            base_price: Uint128(0),
+            coefficient: Decimal::from_str("3").unwrap(),
+        })
+        .validate()
+        .is_err());
+        assert!(pricing(Curve::Exponential {
+            base_price: Uint128(1),
+            growth: Decimal::zero(),
+        })
+        .validate()
+        .is_err());
+    }
+}
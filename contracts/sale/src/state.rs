@@ -0,0 +1,449 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{Addr, BlockInfo, OverflowError, Timestamp, Uint128};
+use cw0::Expiration;
+use cw_storage_plus::{Item, Map, U128Key};
+
+use crate::pricing::{mul_div, Pricing};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct State {
+    pub owner: Addr,
+    pub cw20_address: Addr,
+    pub balance: Uint128,
+    pub start: Option<Expiration>,
+    pub end: Option<Expiration>,
+    pub max_per_address: Option<Uint128>,
+    pub min_per_purchase: Option<Uint128>,
+    pub hard_cap: Option<Uint128>,
+    pub tokens_sold: Uint128,
+    /// hex encoded root of the allowlist, buying is restricted while set
+    pub merkle_root: Option<String>,
+    /// when set, purchased tokens are locked and released on this schedule
+    pub vesting: Option<VestingSchedule>,
+    /// when set, the price is computed by this mode instead of the price list
+    pub pricing: Option<Pricing>,
+    /// when set, the balance is sold by batch auction instead of on purchase
+    pub batch_auction: Option<BatchAuction>,
+    /// when set, the balance is shared between depositors once the sale ends
+    pub fair_launch: Option<FairLaunch>,
+    /// when set, payments are escrowed and refunded unless this many tokens sell
+    pub soft_cap: Option<Uint128>,
+    /// when set, proceeds are held by the contract until withdrawn
+    pub escrow_proceeds: bool,
+    /// when set, proceeds are shared between these recipients instead of the owner
+    pub split: Option<ProceedsSplit>,
+    /// when set, this platform fee is taken from the proceeds of each purchase
+    pub fee: Option<Fee>,
+    /// when set, buyers can name a referrer who is rewarded for the purchase
+    pub referral: Option<Referral>,
+    /// buying is refused until the sale is funded and priced
+    pub status: Status,
+    /// when set, the balance is synced with the live cw20 balance before buys and withdrawals
+    pub live_balance: bool,
+    /// when set, buying, bids, deposits and the receive hook are refused until the owner unpauses
+    pub paused: bool,
+    /// when set, this address can pause the sale but not unpause it
+    pub guardian: Option<Addr>,
+}
+
+impl State {
+    pub fn phase(&self, block: &BlockInfo) -> Phase {
+        if let Some(start) = self.start {
+            if !start.is_expired(block) {
+                return Phase::Pending;
+            }
+        }
+        if let Some(end) = self.end {
+            if end.is_expired(block) {
+                return Phase::Ended;
+            }
+        }
+        Phase::Open
+    }
+
+    /// Whether enough tokens sold to meet the soft cap, if any.
+    pub fn soft_cap_reached(&self) -> bool {
+        self.soft_cap.map_or(true, |cap| self.tokens_sold >= cap)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Phase {
+    Pending,
+    Open,
+    Ended,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    Uninitialized,
+    Initialized,
+}
+
+pub const STATE: Item<State> = Item::new("state");
+
+/// Tokens sold but not yet sent to their buyers, held by the contract outside
+/// of the balance.
+pub const OWED: Item<Uint128> = Item::new("owed");
+
+/// Total cw20 tokens bought by each address.
+pub const PURCHASES: Map<&Addr, Uint128> = Map::new("purchases");
+
+/// Platform fee in basis points of the proceeds, only `collector` can change it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Fee {
+    pub collector: Addr,
+    pub bps: u16,
+}
+
+pub const MAX_BPS: u16 = 10_000;
+
+impl Fee {
+    pub fn amount(&self, proceeds: Uint128) -> Uint128 {
+        proceeds.multiply_ratio(self.bps, MAX_BPS)
+    }
+}
+
+/// Reward for referring a purchase, in basis points of the purchase.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Referral {
+    pub bps: u16,
+    pub reward: ReferralReward,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReferralReward {
+    /// a share of the payment, taken from the proceeds
+    Proceeds,
+    /// bonus tokens on top of those bought, taken from the balance and counted
+    /// against the hard cap
+    Tokens,
+}
+
+/// Purchases made through each referrer and the tokens they bought.
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq, JsonSchema)]
+pub struct ReferralStats {
+    pub referrals: u64,
+    pub volume: Uint128,
+}
+
+pub const REFERRALS: Map<&Addr, ReferralStats> = Map::new("referrals");
+
+/// A recipient of the proceeds and its weight against the other recipients.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Recipient {
+    pub address: Addr,
+    pub weight: u64,
+}
+
+/// Proceeds shared by weight, whatever rounding leaves goes to `remainder`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ProceedsSplit {
+    pub recipients: Vec<Recipient>,
+    pub remainder: Addr,
+}
+
+impl ProceedsSplit {
+    /// Shares `amount` between the recipients, leaving out empty shares.
+    pub fn split(&self, amount: Uint128) -> Vec<(Addr, Uint128)> {
+        let total: u128 = self.recipients.iter().map(|r| r.weight as u128).sum();
+        let mut shares: Vec<(Addr, Uint128)> = self
+            .recipients
+            .iter()
+            .map(|r| (r.address.clone(), amount.multiply_ratio(r.weight, total)))
+            .collect();
+        let shared: u128 = shares.iter().map(|(_, share)| share.u128()).sum();
+        let remainder = Uint128(amount.u128() - shared);
+        match shares
+            .iter_mut()
+            .find(|(address, _)| *address == self.remainder)
+        {
+            Some((_, share)) => *share += remainder,
+            None => shares.push((self.remainder.clone(), remainder)),
+        }
+        shares.retain(|(_, share)| *share != Uint128(0));
+        shares
+    }
+}
+
+/// Native funds paid by each buyer per denom while escrowed under a soft cap.
+pub const PAYMENTS: Map<(&Addr, &str), Uint128> = Map::new("payments");
+
+/// Native proceeds held by the contract per denom.
+pub const PROCEEDS: Map<&str, Uint128> = Map::new("proceeds");
+
+/// Payment cw20 proceeds held by the contract, keyed by the cw20 address.
+pub const CW20_PROCEEDS: Map<&Addr, Uint128> = Map::new("cw20_proceeds");
+
+/// Platform fees per denom taken from escrowed payments, paid to the collector
+/// once the soft cap is met.
+pub const FEES: Map<&str, Uint128> = Map::new("fees");
+
+/// Tokens bought by each buyer, held until the soft cap is met.
+pub const HELD: Map<&Addr, Uint128> = Map::new("held");
+
+/// Price of a single cw20 token, keyed by the accepted native denom.
+pub const PRICES: Map<&str, Uint128> = Map::new("prices");
+
+/// Price of a single cw20 token, keyed by the accepted payment cw20 address.
+pub const CW20_PRICES: Map<&Addr, Uint128> = Map::new("cw20_prices");
+
+/// An ownership transfer waiting to be accepted by the proposed owner.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingOwner {
+    pub owner: Addr,
+    pub expiry: Option<Expiration>,
+}
+
+pub const PENDING_OWNER: Item<PendingOwner> = Item::new("pending_owner");
+
+/// Cliff + linear release: nothing vests before `cliff`, then tokens vest
+/// linearly from `start` until everything is released at `end`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+pub struct VestingSchedule {
+    pub start: Timestamp,
+    pub cliff: Timestamp,
+    pub end: Timestamp,
+}
+
+impl VestingSchedule {
+    pub fn is_valid(&self) -> bool {
+        self.start <= self.cliff && self.cliff <= self.end && self.start < self.end
+    }
+
+    pub fn vested_amount(&self, total: Uint128, now: Timestamp) -> Result<Uint128, OverflowError> {
+        if now < self.cliff {
+            Ok(Uint128(0))
+        } else if now >= self.end {
+            Ok(total)
+        } else {
+            let seconds = |time: Timestamp| u128::from(time.nanos() / 1_000_000_000);
+            let vested = mul_div(
+                total.u128(),
+                seconds(now) - seconds(self.start),
+                seconds(self.end) - seconds(self.start),
+            )?;
+            Ok(Uint128(vested))
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq, JsonSchema)]
+pub struct VestingAccount {
+    pub total: Uint128,
+    pub claimed: Uint128,
+}
+
+/// Tokens bought and claimed by each buyer while the sale vests.
+pub const VESTING: Map<&Addr, VestingAccount> = Map::new("vesting");
+
+/// Bids in `denom` are collected over the sale window, then every filled bid
+/// pays the same clearing price, never below `reserve_price`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BatchAuction {
+    pub denom: String,
+    pub reserve_price: Uint128,
+}
+
+/// Tokens wanted and the most paid per token, `amount * max_price` is escrowed.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Bid {
+    pub amount: Uint128,
+    pub max_price: Uint128,
+}
+
+impl Bid {
+    pub fn escrow(&self) -> Result<Uint128, OverflowError> {
+        self.amount.checked_mul(self.max_price)
+    }
+}
+
+/// Open bid of each bidder, removed once settled.
+pub const BIDS: Map<&Addr, Bid> = Map::new("bids");
+
+/// Tokens bid at each price, so finalization can walk prices from the top.
+pub const DEMAND: Map<U128Key, Uint128> = Map::new("demand");
+
+/// Progress of the auction finalization, which walks the price levels from
+/// the highest bid down until demand covers the supply.
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq, JsonSchema)]
+pub struct Clearing {
+    pub supply: Uint128,
+    /// tokens bid above the next level to walk, all of them filled
+    pub filled: Uint128,
+    /// lowest price level walked so far
+    pub cursor: Option<Uint128>,
+    /// uniform price paid by filled bids, set once finalized
+    pub price: Option<Uint128>,
+    /// tokens bid at the clearing price and the share of the supply left to them
+    pub marginal_demand: Uint128,
+    pub marginal_supply: Uint128,
+    /// marginal demand settled so far
+    pub marginal_settled: Uint128,
+}
+
+impl Clearing {
+    pub fn sold(&self) -> Uint128 {
+        self.filled + self.marginal_supply
+    }
+
+    /// Tokens allocated to `bid` once finalized. Bids at the clearing price
+    /// share what is left pro rata, rounded against the running total so the
+    /// allocations add up to exactly `marginal_supply`.
+    pub fn allocate(&mut self, bid: &Bid) -> Result<Uint128, OverflowError> {
+        let price = match self.price {
+            Some(price) => price,
+            None => return Ok(Uint128(0)),
+        };
+        if bid.max_price > price {
+            Ok(bid.amount)
+        } else if bid.max_price == price {
+            let before = mul_div(
+                self.marginal_supply.u128(),
+                self.marginal_settled.u128(),
+                self.marginal_demand.u128(),
+            )?;
+            self.marginal_settled = self.marginal_settled.checked_add(bid.amount)?;
+            let after = mul_div(
+                self.marginal_supply.u128(),
+                self.marginal_settled.u128(),
+                self.marginal_demand.u128(),
+            )?;
+            Ok(Uint128(after - before))
+        } else {
+            Ok(Uint128(0))
+        }
+    }
+}
+
+pub const CLEARING: Item<Clearing> = Item::new("clearing");
+
+/// Deposits in `denom` are collected over the sale window, then the tokens on
+/// sale are shared pro rata at no more than `price` per token and whatever a
+/// deposit did not buy goes back to the depositor.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FairLaunch {
+    pub denom: String,
+    pub price: Uint128,
+}
+
+/// Funds deposited by each address during a fair launch.
+pub const DEPOSITS: Map<&Addr, Uint128> = Map::new("deposits");
+
+/// Whether a depositor has claimed their tokens and taken their refund.
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq, JsonSchema)]
+pub struct Settlement {
+    pub claimed: bool,
+    pub refunded: bool,
+}
+
+pub const SETTLEMENTS: Map<&Addr, Settlement> = Map::new("settlements");
+
+/// Fair launch totals. `sold` and `raised` are fixed when the first deposit
+/// is settled after the sale ends.
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq, JsonSchema)]
+pub struct FairLaunchTotals {
+    pub deposits: Uint128,
+    pub sold: Option<Uint128>,
+    pub raised: Uint128,
+    /// deposits claimed and refunded so far
+    pub claimed_deposits: Uint128,
+    pub refunded_deposits: Uint128,
+}
+
+impl FairLaunchTotals {
+    /// Tokens bought by `deposit`, its pro rata share of everything sold.
+    pub fn claim(&mut self, deposit: Uint128) -> Result<Uint128, OverflowError> {
+        let sold = self.sold.unwrap_or_default();
+        share(sold, &mut self.claimed_deposits, deposit, self.deposits)
+    }
+
+    /// Part of `deposit` not spent on tokens.
+    pub fn refund(&mut self, deposit: Uint128) -> Result<Uint128, OverflowError> {
+        let spent = share(
+            self.raised,
+            &mut self.refunded_deposits,
+            deposit,
+            self.deposits,
+        )?;
+        deposit.checked_sub(spent)
+    }
+}
+
+/// Share of `total` for `deposit`, rounded against the running `settled`
+/// deposits so the shares add up to exactly `total`.
+fn share(
+    total: Uint128,
+    settled: &mut Uint128,
+    deposit: Uint128,
+    deposits: Uint128,
+) -> Result<Uint128, OverflowError> {
+    let before = mul_div(total.u128(), settled.u128(), deposits.u128())?;
+    *settled = settled.checked_add(deposit)?;
+    let after = mul_div(total.u128(), settled.u128(), deposits.u128())?;
+    Ok(Uint128(after - before))
+}
+
+pub const FAIR_LAUNCH: Item<FairLaunchTotals> = Item::new("fair_launch");
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOKEN: u128 = 1_000_000_000_000_000_000;
+
+    #[test]
+    fn allocate_18_decimal_marginal_bids() {
+        // 1,000 tokens left for 2,000 tokens bid at the clearing price
+        let mut clearing = Clearing {
+            price: Some(Uint128(2)),
+            marginal_demand: Uint128(2_000 * TOKEN),
+            marginal_supply: Uint128(1_000 * TOKEN),
+            ..Clearing::default()
+        };
+        let bid = Bid {
+            amount: Uint128(1_000 * TOKEN),
+            max_price: Uint128(2),
+        };
+        assert_eq!(Uint128(500 * TOKEN), clearing.allocate(&bid).unwrap());
+        assert_eq!(Uint128(500 * TOKEN), clearing.allocate(&bid).unwrap());
+    }
+
+    #[test]
+    fn vest_18_decimal_total() {
+        let start = Timestamp::from_seconds(1_000);
+        let schedule = VestingSchedule {
+            start,
+            cliff: start,
+            end: start.plus_seconds(365 * 24 * 3600),
+        };
+        let total = Uint128(100_000 * TOKEN);
+        let halfway = start.plus_seconds(365 * 12 * 3600);
+        assert_eq!(
+            Uint128(50_000 * TOKEN),
+            schedule.vested_amount(total, halfway).unwrap()
+        );
+        assert_eq!(total, schedule.vested_amount(total, schedule.end).unwrap());
+    }
+
+    #[test]
+    fn share_18_decimal_deposits() {
+        // 3,000 deposited for 1,000 tokens at 2
+        let mut totals = FairLaunchTotals {
+            deposits: Uint128(3_000 * TOKEN),
+            sold: Some(Uint128(1_000 * TOKEN)),
+            raised: Uint128(2_000 * TOKEN),
+            ..FairLaunchTotals::default()
+        };
+        let deposit = Uint128(1_500 * TOKEN);
+        assert_eq!(Uint128(500 * TOKEN), totals.claim(deposit).unwrap());
+        assert_eq!(Uint128(500 * TOKEN), totals.refund(deposit).unwrap());
+        assert_eq!(Uint128(500 * TOKEN), totals.claim(deposit).unwrap());
+        assert_eq!(Uint128(500 * TOKEN), totals.refund(deposit).unwrap());
+    }
+}